@@ -5,6 +5,8 @@ from __future__ import annotations
 
 import argparse
 import asyncio
+import datetime
+import json
 import logging
 import os
 import sys
@@ -14,8 +16,13 @@ from queue import Queue
 import colorlog
 from db import DatabaseConnection
 from lib import Session
+from message import Level, Message
+
+__version__ = "1.0.0"
 
 REPORT = 15
+# Exit code when no tool matched the name, the server answers relints with 404 for it
+EXIT_NOT_FOUND = 3
 
 
 def configure_logging(color: bool, log_level: str) -> None:
@@ -66,6 +73,40 @@ def configure_logging(color: bool, log_level: str) -> None:
         root_logger.addHandler(console_handler)
 
 
+def print_queue_json(queue: Queue) -> bool:
+    """Print messages from queue as JSON lines in the shape of database rows. Empties the queue as a side effect.
+
+    Args:
+    ----
+        queue (Queue): Queue
+
+    Returns:
+    -------
+        bool (bool): True if any messages have been received.
+    """
+    returned_atleast_one_value = False
+    time = int(datetime.datetime.now(tz=datetime.timezone.utc).timestamp())
+
+    while not queue.empty():
+        item: Message = queue.get()
+        returned_atleast_one_value = True
+
+        if item.level == Level.LinterInternal:
+            continue
+
+        row = {
+            "time": time,
+            "tool": item.tool,
+            "code": item.code,
+            "location": item.location,
+            "text": item.body,
+            "level": int(item.level),
+        }
+        print(json.dumps(row), flush=True)
+
+    return returned_atleast_one_value
+
+
 def parse_arguments(arguments: Sequence[str]) -> argparse.Namespace:
     """Parse CLI arguments. Hard exits when arguments are not valid.
 
@@ -86,6 +127,12 @@ def parse_arguments(arguments: Sequence[str]) -> argparse.Namespace:
         help="Specify the name of the tool. Use '-' to read names of multiple tools from stdin.",
         nargs="?",
     )
+    parser.add_argument(
+        "--version",
+        action="version",
+        version=__version__,
+        help="Print the linter version and exit.",
+    )
     parser.add_argument(
         "--log-level",
         "-l",
@@ -121,6 +168,11 @@ def parse_arguments(arguments: Sequence[str]) -> argparse.Namespace:
         action="store_true",
         help="Enable this option to make the program exit with error code 1 if any errors are encountered during execution.",
     )
+    parser.add_argument(
+        "--dry-run",
+        action="store_true",
+        help="Enable this option to print the findings as JSON lines to stdout instead of saving them to the database.",
+    )
     parser.add_argument(
         "--no-color",
         action="store_false",
@@ -165,6 +217,9 @@ async def main(argv: Sequence[str]) -> int:
     database_credentials: str = (
         os.environ["DATABASE_URL"] if "DATABASE_URL" in os.environ else args.db
     )
+    # A dry run never connects, so nothing is deleted or inserted
+    if args.dry_run:
+        database_credentials = None
     lint_all: str = args.lint_all
     tool_name: str = args.name
     exact: str = args.exact
@@ -176,6 +231,7 @@ async def main(argv: Sequence[str]) -> int:
     session = Session()
     db = DatabaseConnection(database_credentials, database_credentials is None)
     message_queue = Queue()
+    store_messages = print_queue_json if args.dry_run else db.insert_from_queue
     returned_at_least_one_error: bool = False
 
     # Start linting loop
@@ -205,7 +261,7 @@ async def main(argv: Sequence[str]) -> int:
 
             await session.lint_all_tools(return_q=message_queue)
             page += 10
-            returned_at_least_one_error = db.insert_from_queue(message_queue)
+            returned_at_least_one_error = store_messages(message_queue)
 
             db.commit()
     else:
@@ -224,7 +280,7 @@ async def main(argv: Sequence[str]) -> int:
 
         if count == 0:
             logging.critical(f"Found {count} tools, exiting")
-            return 1
+            return EXIT_NOT_FOUND
 
         logging.info(f"Found {count} tools")
 
@@ -234,7 +290,7 @@ async def main(argv: Sequence[str]) -> int:
             db.drop_rows_with_tool_name(name)
 
         await session.lint_all_tools(return_q=message_queue)
-        returned_at_least_one_error = db.insert_from_queue(message_queue)
+        returned_at_least_one_error = store_messages(message_queue)
 
         db.commit()
 
@@ -38,6 +38,18 @@ class DatabaseConnection:
         create_table_query = "CREATE TABLE IF NOT EXISTS messages ( id SERIAL PRIMARY KEY, time BIGINT NOT NULL, tool TEXT NOT NULL, code TEXT NOT NULL, location TEXT NOT NULL, text TEXT NOT NULL, level INTEGER NOT NULL );"
         cursor.execute(create_table_query)
 
+        # Lets the server answer code and time filters of a search in one index scan
+        create_index_query = "CREATE INDEX IF NOT EXISTS messages_code_time ON messages ( code text_pattern_ops, time );"
+        cursor.execute(create_index_query)
+
+        # Lets the server page through the newest findings from a cursor without sorting
+        create_time_index_query = "CREATE INDEX IF NOT EXISTS messages_time_id ON messages ( time DESC, id );"
+        cursor.execute(create_time_index_query)
+
+        # The server looks tools up by their lowercase biotoolsID
+        create_tool_index_query = "CREATE INDEX IF NOT EXISTS messages_tool_lower ON messages ( LOWER(tool) );"
+        cursor.execute(create_tool_index_query)
+
         self.connection = conn
         self.cursor = cursor
 
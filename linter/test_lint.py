@@ -140,6 +140,9 @@ async def test_cli():
     assert await cli.main(["msmc"]) == 0
     assert await cli.main(["metexplore", "--no-color"]) == 0
     assert await cli.main(["metexplore", "--exit-on-error"]) == 1
+    assert (
+        await cli.main(["this-tool-does-not-exist", "--exact"]) == cli.EXIT_NOT_FOUND
+    )
 
 
 # Test url.py
@@ -0,0 +1,14 @@
+use std::process::Command;
+
+/// Embed the commit the server is built from as `GIT_HASH`, left unset outside of a checkout
+fn main() {
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    println!("cargo:rerun-if-changed=../.git/packed-refs");
+
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output();
+    if let Some(output) = output.ok().filter(|x| x.status.success()) {
+        let hash = String::from_utf8_lossy(&output.stdout);
+        println!("cargo:rustc-env=GIT_HASH={}", hash.trim());
+    }
+}
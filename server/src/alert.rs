@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+use tracing::{error, info};
+
+use crate::db;
+
+/// Tracks whether the critical count is above the threshold so an alert is sent once per crossing
+pub struct CriticalAlert {
+    /// Alert when the critical count exceeds this value
+    pub threshold: i64,
+    /// Whether an alert has already been sent for the current crossing
+    pub triggered: bool,
+}
+
+impl CriticalAlert {
+    pub fn new(threshold: i64) -> Self {
+        Self {
+            threshold,
+            triggered: false,
+        }
+    }
+
+    /// Update with a new count, returns true if an alert should be sent.
+    ///
+    /// To avoid flapping around the threshold the alert is only re-armed
+    /// once the count drops to 90% of the threshold or lower.
+    pub fn update(&mut self, count: i64) -> bool {
+        if self.triggered {
+            if count <= self.threshold * 9 / 10 {
+                self.triggered = false;
+            }
+            return false;
+        }
+
+        if count > self.threshold {
+            self.triggered = true;
+            return true;
+        }
+
+        false
+    }
+}
+
+/// Periodically check the critical message count and notify the webhook when it crosses the threshold
+pub async fn watch_critical_count(
+    pool: Pool<Postgres>,
+    threshold: i64,
+    webhook: String,
+    interval: Duration,
+) {
+    let client = reqwest::Client::new();
+    let mut alert = CriticalAlert::new(threshold);
+    let mut timer = tokio::time::interval(interval);
+
+    loop {
+        timer.tick().await;
+
+        let count = match db::count_critical_messages(&pool).await {
+            Ok(count) => count,
+            Err(e) => {
+                error!("Could not count critical findings: {e}");
+                continue;
+            }
+        };
+        if !alert.update(count) {
+            continue;
+        }
+
+        info!("Critical count {count} exceeded threshold {threshold}, sending alert");
+
+        // `text` is what Slack compatible webhooks display
+        let body = json!({
+            "text": format!("Biotools linter: {count} critical findings, threshold is {threshold}"),
+            "critical_count": count,
+            "threshold": threshold,
+        });
+
+        if let Err(e) = client.post(&webhook).json(&body).send().await {
+            error!("Could not send critical count alert: {e}");
+        }
+    }
+}
@@ -1,42 +1,72 @@
 use axum::{
+    body::{Bytes, HttpBody},
     extract::{ConnectInfo, Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::Html,
+    http::{HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{Html, Response},
     Json,
 };
-use chrono::{DateTime, Utc};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Datelike, Days, NaiveTime, Utc,
+};
 use db::DatabaseEntry;
 
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_json::{Map, Value};
 
 use axum::response::IntoResponse;
+use futures_util::StreamExt;
 use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
     fs,
+    hash::{Hash, Hasher},
+    io::{BufRead, BufReader, Read},
     net::SocketAddr,
     path::{Component, PathBuf},
-    process::Command,
+    process::{Command, Output, Stdio},
     str::FromStr,
-    time::{Duration, UNIX_EPOCH},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 use tera::{Context, Tera};
-use tokio::join;
+use tokio::{join, sync::broadcast};
 
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use axum::http::header;
 use utoipa::{IntoParams, ToSchema};
 
-use serde_repr::{Deserialize_repr, Serialize_repr};
+use serde_repr::Serialize_repr;
+use sha2::{Digest, Sha256};
 use sitewriter::{ChangeFreq, UrlEntry};
+use subtle::ConstantTimeEq;
 
+use crate::codes::{self, ErrorCode, ERROR_CODES};
 use crate::db;
+use crate::jobs::{self, EnqueueError, LintJob};
+use crate::linter::{publications, urls, Finding};
+use crate::metrics;
+use crate::sarif;
+use crate::webhook;
 use crate::ServerState;
 
 /// Macro to log important information on a http method
 /// Needs `headers: HeaderMap` in argument
+///
+/// Read requests prefixed with `sampled` are only logged at `--log-sample-rate`,
+/// failed requests are logged regardless by `log_failed_requests`.
 macro_rules! info_statement {
+    (sampled $state:expr, $headers:tt, $name:tt, $($arg:tt)*) => {
+        if sample_request_log($state.config.log_sample_rate) {
+            info_statement!($headers, $name, $($arg)*);
+        }
+    };
     ($headers:tt, $name:tt, $($arg:tt)*) => {
         // Get sender IP, prioritize X-Real-IP because of nginx
         let ip: String = match $headers.contains_key("X-Real-IP") {
@@ -59,43 +89,77 @@ macro_rules! info_statement {
             false => String::from("?"),
         };
 
+        // Set by `request_id` on every request
+        let request_id = $headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|x| x.to_str().ok())
+            .unwrap_or("?");
+
         let custom_message = format!($($arg)*);
 
-        info!("HTTP {} ({custom_message}) FROM IP `{ip}` UA `{ua}`", $name);
+        info!("HTTP {} ({custom_message}) FROM IP `{ip}` UA `{ua}` REQUEST `{request_id}`", $name);
     };
 }
 
-static ERROR_CODES: [&str; 20] = [
-    "URL_INVALID",
-    "URL_PERMANENT_REDIRECT",
-    "URL_BAD_STATUS",
-    "URL_NO_SSL",
-    "URL_UNUSED_SSL",
-    "URL_TIMEOUT",
-    "URL_SSL_ERROR",
-    "URL_CONN_ERROR",
-    "URL_LINTER_ERROR",
-    "EDAM_OBSOLETE",
-    "EDAM_NOT_RECOMMENDED",
-    "EDAM_INVALID",
-    "DOI_BUT_NOT_PMID",
-    "DOI_BUT_NOT_PMCID",
-    "PMID_BUT_NOT_DOI",
-    "PMCID_BUT_NOT_DOI",
-    "URL_TOO_MANY_REDIRECTS",
-    "EDAM_TOPIC_DISCREPANCY",
-    "EDAM_INPUT_DISCREPANCY",
-    "EDAM_OUTPUT_DISCREPANCY",
-];
+/// Header holding the ID of a request, taken from the client or generated by `request_id`
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longest request ID accepted from a client, longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Exit code of linter/cli.py when no tool matched, keep in sync with `EXIT_NOT_FOUND`
+const LINTER_EXIT_NOT_FOUND: i32 = 3;
+
+/// How long the category summary is served from memory
+const CATEGORY_SUMMARY_TTL: Duration = Duration::from_secs(60);
+
+/// How long the error code counts are served from memory
+const ERROR_CODE_STATS_TTL: Duration = Duration::from_secs(60);
+
+/// Most common error codes listed on the main page
+const INDEX_TOP_CODES: i64 = 5;
+
+/// How long the dataset metrics are served from memory
+const DATA_METRICS_TTL: Duration = Duration::from_secs(60);
+
+/// Categories returned by `code_category`
+const CODE_CATEGORIES: [&str; 4] = ["URL", "EDAM", "DOI-PMID", "other"];
+
+/// Templates rendered by the pages
+pub const TEMPLATES_GLOB: &str = "templates/*.html";
+
+/// Findings included in a tool bundle, the same as a search page
+const TOOL_BUNDLE_MESSAGES: usize = 100;
+
+/// Where tool pages are linked to when `--tool-url-base` is not given
+pub const DEFAULT_TOOL_URL_BASE: &str = "https://bio.tools/";
+
+/// Sampled requests so far, decides which of them are logged
+static SAMPLED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+/// Tools accepted by one batch request
+const BATCH_TOOLS: usize = 50;
+
+/// Findings returned per tool by a batch request
+const BATCH_TOOL_MESSAGES: usize = 100;
+
+/// Directory the linter runs in, relative to the server
+const LINTER_DIR: &str = "../";
+
+/// Longest a `/healthz` database probe may take before it counts as failed
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lines of linter stderr kept for a failed relint
+const RELINT_ERROR_LINES: usize = 50;
 
 // Initialize and cache templates and regex
 lazy_static! {
-    pub static ref TEMPLATES: Tera = {
-        match Tera::new("templates/*.html") {
-            Ok(t) => t,
-            Err(e) => {
-                panic!("Parsing error(s): {e}");
-            }
+    // Checked on startup, so rendering never finds them broken
+    pub static ref PARSED_TEMPLATES: Result<Tera, String> = load_templates(TEMPLATES_GLOB);
+    pub static ref TEMPLATES: &'static Tera = match &*PARSED_TEMPLATES {
+        Ok(t) => t,
+        Err(e) => {
+            panic!("Parsing error(s): {e}");
         }
     };
     pub static ref LINK_REGEX: Regex = {
@@ -104,35 +168,91 @@ lazy_static! {
         )
         .unwrap()
     };
+    // Regex taken from https://biotools.readthedocs.io/en/latest/api_usage_guide.html?highlight=biotoolsid#biotoolsid
+    pub static ref BIOTOOLS_ID_REGEX: Regex = Regex::new(r"^[_\-.0-9a-zA-Z]+$").unwrap();
+    // Parsed once instead of on every formatted message
+    static ref TIMESTAMP_FORMAT: Vec<Item<'static>> = StrftimeItems::new("%Y-%m-%d %H:%M").collect();
+    // Paths replaced in logged linter output, the linter directory first as it may be inside the home directory
+    static ref REDACTED_PATHS: Vec<(String, &'static str)> = {
+        let mut paths = Vec::new();
+        if let Ok(dir) = fs::canonicalize(LINTER_DIR) {
+            paths.push((dir.display().to_string(), "<linter>"));
+        }
+        if let Some(home) = std::env::var("HOME").ok().filter(|x| x.len() > 1) {
+            paths.push((home, "~"));
+        }
+        paths
+    };
+    // Content hash of the stylesheet, changes whenever the file does so it can be cached forever
+    pub static ref STYLE_HASH: String = {
+        let mut hasher = DefaultHasher::new();
+        fs::read("static/style.css")
+            .expect("Could not read static/style.css")
+            .hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    };
+}
+
+/// Format a unix timestamp as `%Y-%m-%d %H:%M`, timestamps out of range give an empty string
+pub fn format_timestamp(time: i64) -> String {
+    DateTime::<Utc>::from_timestamp(time, 0)
+        .map(|x| x.format_with_items(TIMESTAMP_FORMAT.iter()).to_string())
+        .unwrap_or_default()
+}
+
+/// Parse every template matching `glob`, the error includes the cause of the failure
+pub fn load_templates(glob: &str) -> Result<Tera, String> {
+    Tera::new(glob).map_err(|e| {
+        let mut message = e.to_string();
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            message += &format!(": {cause}");
+            source = cause.source();
+        }
+        message
+    })
+}
+
+/// Versioned URL of the stylesheet, e.g. `/style.<hash>.css`
+pub fn style_url() -> String {
+    format!("/style.{}.css", *STYLE_HASH)
+}
+
+/// Template context with the static asset URLs used by `base.html`
+fn template_context() -> Context {
+    let mut c = Context::new();
+    c.insert("style_url", &style_url());
+    c
 }
 
-#[derive(Debug, Serialize_repr, Deserialize_repr, ToSchema, Clone, Copy)]
+#[derive(Debug, Serialize_repr, ToSchema, Clone, Copy)]
 #[repr(u8)]
 /// Enumerable severity score
 /// - Error (1) -> Obsolete, no longer used
 /// - LinterError (2) -> Uncaught linter error
-/// - ReportCritical (4) -> Indicates a critical error reserved for security vulnerabilities.
+/// - ReportCritical (8) -> Indicates a critical error reserved for security vulnerabilities.
 /// - ReportHigh (5) -> Represents a high-severity error.
 /// - ReportMedium (6) -> Represents a medium-severity error.
 /// - ReportLow (7) -> Represents a low-severity error.
+/// - Unknown (0) -> A level this server doesn't know, the stored value is kept in `Message::level`
+///
+/// The legacy levels 1 and 2 are never hidden: searches without `severity` include them,
+/// messages and downloads carry their numeric value like every other level, and
+/// severity counts report them as `error` and `linter_error`.
 pub enum Severity {
-    /// Obsolete, now used as an API error
-    #[serde(rename = "Error")]
+    /// Stored level not known to this server, e.g. one added to the linter later
+    Unknown = 0,
+    /// Obsolete level 1, no longer produced by the linter
     Error = 1,
     /// Uncaught linter error
-    #[serde(rename = "LinterError")]
     LinterError = 2,
     /// Indicates a critical error reserved for security vulnerabilities.
-    #[serde(rename = "ReportCritical")]
     ReportCritical = 8,
     /// Represents a high-severity error.
-    #[serde(rename = "ReportHigh")]
     ReportHigh = 5,
     /// Represents a medium-severity error.
-    #[serde(rename = "ReportMedium")]
     ReportMedium = 6,
     /// Represents a low-severity error.
-    #[serde(rename = "ReportLow")]
     ReportLow = 7,
 }
 
@@ -144,7 +264,8 @@ impl From<i32> for Severity {
             5 => Self::ReportHigh,
             6 => Self::ReportMedium,
             7 => Self::ReportLow,
-            _ => Self::Error,
+            1 => Self::Error,
+            _ => Self::Unknown,
         }
     }
 }
@@ -157,18 +278,128 @@ impl From<Severity> for i32 {
             Severity::ReportMedium => 6,
             Severity::ReportLow => 7,
             Severity::Error => 1,
+            Severity::Unknown => 0,
+        }
+    }
+}
+
+impl Severity {
+    /// Parse either the numeric value or the name of a severity
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Error" => Some(Self::Error),
+            "LinterError" => Some(Self::LinterError),
+            "ReportCritical" => Some(Self::ReportCritical),
+            "ReportHigh" => Some(Self::ReportHigh),
+            "ReportMedium" => Some(Self::ReportMedium),
+            "ReportLow" => Some(Self::ReportLow),
+            _ => Self::from_value(value.parse().ok()?),
+        }
+    }
+
+    fn from_value(value: i64) -> Option<Self> {
+        match value {
+            1 | 2 | 5..=8 => Some(Self::from(value as i32)),
+            _ => None,
+        }
+    }
+}
+
+/// Accepts the numeric value used in `Message` output as well as the name, e.g. `5` or `ReportHigh`
+///
+/// `0` is `Unknown`, output of a level this server doesn't know still reads back.
+impl<'de> Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SeverityVisitor;
+
+        impl de::Visitor<'_> for SeverityVisitor {
+            type Value = Severity;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a severity name or numeric value")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Severity, E> {
+                match v {
+                    0 => Ok(Severity::Unknown),
+                    v => Severity::from_value(v)
+                        .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(v), &self)),
+                }
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Severity, E> {
+                match v {
+                    0 => Ok(Severity::Unknown),
+                    v => Severity::from_value(v.try_into().unwrap_or(-1))
+                        .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Severity, E> {
+                Severity::parse(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(SeverityVisitor)
+    }
+}
+
+/// Column searched by the `query` of the search endpoint
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    /// Tool ID
+    Tool,
+    /// Error code
+    Code,
+    /// Human readable error text
+    Text,
+    /// Position of the error in the tool's JSON, e.g. `publication[0]`
+    Location,
+    /// Tool ID or error code
+    #[default]
+    All,
+}
+
+impl SearchField {
+    /// Whether the tool, code, text and location columns are searched
+    pub fn columns(self) -> (bool, bool, bool, bool) {
+        match self {
+            Self::Tool => (true, false, false, false),
+            Self::Code => (false, true, false, false),
+            Self::Text => (false, false, true, false),
+            Self::Location => (false, false, false, true),
+            Self::All => (true, true, false, false),
+        }
+    }
+}
+
+impl Severity {
+    /// Order from least to most severe, the numeric values are not ordered by severity
+    pub fn rank(self) -> u8 {
+        match self {
+            Self::Unknown | Self::Error | Self::LinterError => 0,
+            Self::ReportLow => 1,
+            Self::ReportMedium => 2,
+            Self::ReportHigh => 3,
+            Self::ReportCritical => 4,
         }
     }
 }
 
 /// Represents the query parameters needed by the API.
-#[derive(Deserialize, IntoParams)]
+///
+/// Also accepted as a JSON body by `POST /api/search`.
+#[derive(Deserialize, IntoParams, ToSchema, Default)]
 pub struct APIQuery {
     /// A search string used to filter messages (optional).
     ///
     /// If provided, the API will return errors where the tool or error code matches the query (Case insensitive)
     query: Option<String>,
 
+    /// Column the query is matched against (optional, defaults to `all`).
+    field: Option<SearchField>,
+
     /// The page number for pagination (optional).
     ///
     /// Each page contains up to 100 messages. Use this field to specify the
@@ -176,17 +407,481 @@ pub struct APIQuery {
     #[param(style = Simple, minimum = 0)]
     page: Option<i64>,
 
-    /// Optional severity filter
-    severity: Option<Severity>,
+    /// Optional severity filter, a comma-separated list of names or numeric values.
+    ///
+    /// A `!` prefix excludes a severity, e.g. `ReportHigh,ReportCritical` or `!LinterError`.
+    #[param(value_type = Option<String>)]
+    #[schema(value_type = Option<String>)]
+    severity: Option<SeverityFilter>,
 
-    /// Optional error code filter
+    /// Optional error code filter, a comma-separated list matched case-insensitively.
+    ///
+    /// `*` matches any characters, everything else including `_` and `%` matches itself.
+    /// A `!` prefix excludes the codes matched, e.g. `URL_INVALID,EDAM_OBSOLETE` or `!URL_*`.
     code: Option<String>,
+
+    /// Only messages found at or after this time (optional), see `until`.
+    ///
+    /// Combined with `code`, e.g. `code=EDAM_OBSOLETE&since=<7 days ago>`, both are answered in one indexed scan.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    since: Option<i64>,
+
+    /// Only messages found at or before this time (optional).
+    ///
+    /// Like `since` a Unix timestamp or an ISO 8601 date or date and time, e.g. `2024-01-31` or
+    /// `2024-01-31T12:00:00Z`. Times without an offset are UTC and dates are their midnight.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    until: Option<i64>,
+
+    /// Return the values as stored in the database, without HTML escaping and autolinking (optional).
+    raw: Option<bool>,
+
+    /// Only count the matching messages and return `{count}` (optional).
+    count_only: Option<bool>,
+
+    /// Continue after the page that returned this `next_cursor`, replaces `page` (optional).
+    ///
+    /// Unlike `page` it stays fast on deep pages and has no maximum offset.
+    /// Only available with the default sort.
+    cursor: Option<String>,
+
+    /// Column the results are sorted by (optional, default `time`).
+    sort: Option<SearchSort>,
+
+    /// Direction of the sort (optional), by default newest and most severe first,
+    /// tools and codes alphabetically.
+    order: Option<SortDirection>,
+}
+
+/// Column search results are sorted by, ties are ordered newest first
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// Time the finding was found
+    #[default]
+    Time,
+    /// Severity, see `Severity::rank`
+    Severity,
+    /// Tool ID
+    Tool,
+    /// Error code
+    Code,
+}
+
+impl SearchSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::Severity => "severity",
+            Self::Tool => "tool",
+            Self::Code => "code",
+        }
+    }
+
+    /// Direction used when the request has no `order`
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            Self::Time | Self::Severity => SortDirection::Desc,
+            Self::Tool | Self::Code => SortDirection::Asc,
+        }
+    }
+}
+
+/// Direction of a sort
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Order of a search page, newest first by default
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchOrder {
+    pub sort: SearchSort,
+    pub direction: SortDirection,
+}
+
+impl Default for SearchOrder {
+    fn default() -> Self {
+        Self {
+            sort: SearchSort::Time,
+            direction: SortDirection::Desc,
+        }
+    }
+}
+
+/// Position after the last message of a search page, ordered by `time DESC, id`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchCursor {
+    pub time: i64,
+    pub id: i32,
+}
+
+/// A page of search results in a given order
+#[derive(Debug, Clone, Copy)]
+pub struct SearchPage {
+    pub start: SearchStart,
+    pub order: SearchOrder,
+}
+
+/// Where a search page starts
+#[derive(Debug, Clone, Copy)]
+pub enum SearchStart {
+    /// Number of messages skipped, a multiple of the 100 messages of a page
+    Offset(i64),
+    /// Right after the last message of the previous page
+    After(SearchCursor),
+}
+
+impl SearchStart {
+    /// Row offset and cursor of the page query
+    pub fn offset_cursor(self) -> (i64, Option<SearchCursor>) {
+        match self {
+            Self::Offset(offset) => (offset, None),
+            Self::After(cursor) => (0, Some(cursor)),
+        }
+    }
+}
+
+/// Clients should treat cursors as opaque, the format may change
+impl std::fmt::Display for SearchCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:x}.{:x}", self.time as u64, self.id as u32)
+    }
+}
+
+impl FromStr for SearchCursor {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (time, id) = s.split_once('.').ok_or(())?;
+        Ok(Self {
+            time: u64::from_str_radix(time, 16).map_err(|_| ())? as i64,
+            id: u32::from_str_radix(id, 16).map_err(|_| ())? as i32,
+        })
+    }
 }
 
 #[derive(Deserialize, IntoParams)]
 pub struct DownloadParams {
     /// A search string used to filter messages (optional).
     query: Option<String>,
+    /// Format of the download (optional, default `csv`).
+    ///
+    /// `bom`, `delimiter` and `tool_url` only apply to CSV, JSON and NDJSON carry every `Message` field.
+    format: Option<DownloadFormat>,
+    /// Return the values as stored in the database, without HTML escaping (optional).
+    raw: Option<bool>,
+    /// Start the CSV with a UTF-8 byte order mark (optional, default false).
+    ///
+    /// Excel needs it to decode non-ASCII characters correctly, but most other
+    /// CSV readers treat it as part of the first column name.
+    bom: Option<bool>,
+    /// Column separator of the CSV (optional, default `comma`).
+    delimiter: Option<CsvDelimiter>,
+    /// Order of the rows (optional, default `time_desc`).
+    sort: Option<SortOrder>,
+    /// Add a `tool_url` column linking to the tool page (optional, default false).
+    tool_url: Option<bool>,
+    /// Only messages found at or after this time (optional), same format as in the search.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    since: Option<i64>,
+    /// Only messages found at or before this time (optional), same format as in the search.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    until: Option<i64>,
+}
+
+/// Severities matched by a search, every level when nothing is included
+#[derive(Debug, Clone, Default)]
+pub struct SeverityFilter {
+    include: Vec<Severity>,
+    exclude: Vec<Severity>,
+}
+
+impl SeverityFilter {
+    /// Parse a comma-separated list of severities, each optionally prefixed with `!`
+    fn parse(value: &str) -> Option<Self> {
+        let mut filter = Self::default();
+        for item in value.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            match item.strip_prefix('!') {
+                Some(x) => filter.exclude.push(Severity::parse(x.trim())?),
+                None => filter.include.push(Severity::parse(item)?),
+            }
+        }
+        Some(filter)
+    }
+
+    /// Stored levels matched by the filter, empty to match every level including unknown ones
+    pub fn include_levels(&self) -> Vec<i32> {
+        self.include.iter().map(|&x| x.into()).collect()
+    }
+
+    /// Stored levels never matched, they take precedence over the included ones
+    pub fn exclude_levels(&self) -> Vec<i32> {
+        self.exclude.iter().map(|&x| x.into()).collect()
+    }
+}
+
+/// Accepts a single numeric value as well as a list, e.g. `5` or `ReportHigh,!LinterError`
+impl<'de> Deserialize<'de> for SeverityFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SeverityFilterVisitor;
+
+        impl de::Visitor<'_> for SeverityFilterVisitor {
+            type Value = SeverityFilter;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a comma-separated list of severity names or numeric values")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<SeverityFilter, E> {
+                Severity::from_value(v)
+                    .map(|x| SeverityFilter {
+                        include: vec![x],
+                        exclude: Vec::new(),
+                    })
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<SeverityFilter, E> {
+                self.visit_i64(v.try_into().unwrap_or(-1))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<SeverityFilter, E> {
+                SeverityFilter::parse(v)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(SeverityFilterVisitor)
+    }
+}
+
+/// Error codes matched by a search as `LIKE` patterns, every code when nothing is included
+#[derive(Debug, Clone)]
+pub struct CodeFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl CodeFilter {
+    /// Parse a comma-separated list of codes, each optionally prefixed with `!`
+    ///
+    /// Codes are stored uppercase, so are the patterns, see `range` for how the code index is used.
+    pub fn parse(value: &str) -> Self {
+        let mut filter = Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+        };
+        for item in value.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let (list, pattern) = match item.strip_prefix('!') {
+                Some(x) => (&mut filter.exclude, x.trim()),
+                None => (&mut filter.include, item),
+            };
+            // Only `*` is a wildcard, `_` is part of most codes
+            let pattern = pattern
+                .to_uppercase()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+                .replace('*', "%");
+            list.push(pattern);
+        }
+        if filter.include.is_empty() {
+            filter.include.push("%".to_owned());
+        }
+        filter
+    }
+
+    /// Bounds of the codes the included patterns can match, compared bytewise
+    ///
+    /// `LIKE ANY` can't be answered from an index, `code ~>=~ from AND code ~<~ to` can by the
+    /// `text_pattern_ops` index on `(code, time)` even in a generic plan. A pattern starting with
+    /// a wildcard matches any code, the upper bound is then above every code.
+    pub fn range(&self) -> (String, String) {
+        let unbounded = (String::new(), char::MAX.to_string());
+        let mut range: Option<(String, String)> = None;
+        for pattern in &self.include {
+            let prefix = like_prefix(pattern);
+            // The smallest string above every string starting with the prefix
+            let mut upper = prefix.clone();
+            let Some(next) = upper.pop().and_then(|x| char::from_u32(x as u32 + 1)) else {
+                return unbounded;
+            };
+            upper.push(next);
+            range = Some(match range {
+                None => (prefix, upper),
+                Some((from, to)) => (from.min(prefix), to.max(upper)),
+            });
+        }
+        range.unwrap_or(unbounded)
+    }
+}
+
+/// Literal start of a `LIKE` pattern, up to the first unescaped wildcard
+fn like_prefix(pattern: &str) -> String {
+    let mut prefix = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' | '_' => break,
+            '\\' => prefix.extend(chars.next()),
+            c => prefix.push(c),
+        }
+    }
+    prefix
+}
+
+impl Default for CodeFilter {
+    fn default() -> Self {
+        Self::parse("")
+    }
+}
+
+/// Messages found between two Unix timestamps, both inclusive
+#[derive(Debug, Clone, Copy)]
+pub struct TimeRange {
+    pub since: i64,
+    pub until: i64,
+}
+
+impl TimeRange {
+    pub fn new(since: Option<i64>, until: Option<i64>) -> Self {
+        Self {
+            since: since.unwrap_or(i64::MIN),
+            until: until.unwrap_or(i64::MAX),
+        }
+    }
+}
+
+impl Default for TimeRange {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+/// Parse a Unix timestamp or an ISO 8601 date, date and time, or date and time with an offset
+pub fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(timestamp) = value.parse() {
+        return Some(timestamp);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.timestamp());
+    }
+    if let Ok(time) = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Some(time.and_utc().timestamp());
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(|x| x.and_time(NaiveTime::MIN).and_utc().timestamp())
+}
+
+/// Accepts a timestamp as a number or as a string understood by `parse_timestamp`
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<i64>, D::Error> {
+    struct TimestampVisitor;
+
+    impl de::Visitor<'_> for TimestampVisitor {
+        type Value = Option<i64>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a Unix timestamp or an ISO 8601 date")
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Option<i64>, E> {
+            Ok(None)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Option<i64>, E> {
+            Ok(Some(v))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Option<i64>, E> {
+            i64::try_from(v)
+                .map(Some)
+                .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Option<i64>, E> {
+            parse_timestamp(v)
+                .map(Some)
+                .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+/// Order of downloaded messages
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Newest first
+    #[default]
+    TimeDesc,
+    /// Oldest first
+    TimeAsc,
+    /// Most severe first, then newest first
+    SeverityDesc,
+    /// Least severe first, then newest first
+    SeverityAsc,
+}
+
+impl SortOrder {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TimeDesc => "time_desc",
+            Self::TimeAsc => "time_asc",
+            Self::SeverityDesc => "severity_desc",
+            Self::SeverityAsc => "severity_asc",
+        }
+    }
+}
+
+/// Format of the download
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadFormat {
+    /// Comma separated values with a header row
+    #[default]
+    Csv,
+    /// One JSON array of `Message`
+    Json,
+    /// One `Message` JSON object per line
+    Ndjson,
+}
+
+impl DownloadFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Json => "application/json",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Column separator of the CSV download
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvDelimiter {
+    #[default]
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+impl CsvDelimiter {
+    pub fn as_byte(self) -> u8 {
+        match self {
+            Self::Comma => b',',
+            Self::Semicolon => b';',
+            Self::Tab => b'\t',
+        }
+    }
 }
 
 /// Represents a single result in the API response.
@@ -197,63 +892,88 @@ pub struct Message {
     /// A human-readable timestamp formatted as `%Y-%m-%d %H:%M`.
     timestamp: String,
     /// The ID of the tool to which the error belongs (valid biotools ID).
-    tool: String,
+    pub tool: String,
+    /// Link to the page of the tool, `https://bio.tools/<tool>` unless the server is configured otherwise
+    pub tool_url: String,
     /// Error code
-    code: String,
+    pub code: String,
     /// Human readable error
-    text: String,
+    pub text: String,
+    /// `text` with links turned into HTML anchors, only set for the web UI search
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    html: Option<String>,
     /// The severity level of the error.
     ///
-    /// - `4` indicates a critical error reserved for security vulnerabilities.
+    /// - `8` indicates a critical error reserved for security vulnerabilities.
     /// - `5` represents a high-severity error.
     /// - `6` represents a medium-severity error.
     /// - `7` represents a low-severity error.
-    severity: Severity,
+    /// - `2` is an uncaught linter error, `1` the obsolete `Error` level.
+    /// - `0` is a level this server doesn't know, see `level`.
+    pub severity: Severity,
+    /// The level as stored in the database, the same as `severity` unless it is unknown
+    level: i32,
 }
 
-/// Convert a database entry into the api message
+/// Convert a database entry into the api message, values are kept as stored
+///
+/// `tool_url` is left empty until `link_tool` is called with the configured base.
 impl From<DatabaseEntry> for Message {
-    fn from(value: DatabaseEntry) -> Self {
-        let mut v = value;
-
-        // Escape
-        v.tool = html_escape::encode_text(&v.tool).to_string();
-        v.code = html_escape::encode_text(&v.code).to_string();
-        v.location = html_escape::encode_text(&v.location).to_string();
-
-        // Autolink
-        let processed_text = LINK_REGEX
-            .replace_all(&v.text, |caps: &regex::Captures| {
-                let url = caps.get(0).unwrap().as_str();
-                format!("<a href=\"{url}\" rel=\"nofollow\" >{url}</a>")
-            })
-            .to_string();
-
-        // Timestamp
-        let d = UNIX_EPOCH + Duration::from_secs(v.time.try_into().unwrap());
-        let datetime = DateTime::<Utc>::from(d);
-        let timestamp = datetime.format("%Y-%m-%d %H:%M").to_string();
+    fn from(v: DatabaseEntry) -> Self {
+        let timestamp = format_timestamp(v.time);
 
         Self {
             code: v.code,
+            tool_url: String::new(),
             tool: v.tool,
-            text: processed_text,
+            text: v.text,
+            html: None,
             timestamp,
             time: v.time,
             #[allow(clippy::cast_possible_truncation)]
             severity: Severity::from(v.level),
+            level: v.level,
         }
     }
 }
 
+impl Message {
+    /// Fill `tool_url` with the page of the tool under `base`
+    pub fn link_tool(&mut self, base: &str) {
+        self.tool_url = tool_url(base, &self.tool);
+    }
+
+    /// HTML escape the tool and code
+    fn escape(&mut self) {
+        self.tool = html_escape::encode_text(&self.tool).to_string();
+        self.tool_url = html_escape::encode_text(&self.tool_url).to_string();
+        self.code = html_escape::encode_text(&self.code).to_string();
+    }
+
+    /// Fill `html` with the autolinked text, the text is escaped first as it is rendered unescaped
+    fn autolink(&mut self) {
+        let text = html_escape::encode_text(&self.text);
+        let html = LINK_REGEX
+            .replace_all(&text, |caps: &regex::Captures| {
+                let url = caps.get(0).unwrap().as_str();
+                // The match is escaped text, the link needs the original URL escaped for an attribute
+                let href = html_escape::decode_html_entities(url);
+                let href = html_escape::encode_double_quoted_attribute(&href);
+                format!("<a href=\"{href}\" rel=\"nofollow\" >{url}</a>")
+            })
+            .to_string();
+        self.html = Some(html);
+    }
+}
+
 /// Statistics data sent from the API
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
 pub struct Statistics {
     pub data: Vec<StatisticsEntry>,
 }
 
 /// A single statistics entry
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
 pub struct StatisticsEntry {
     pub time: u64,
     pub total_count_on_biotools: u64,
@@ -263,308 +983,3087 @@ pub struct StatisticsEntry {
     pub severity: Option<Map<String, Value>>,
 }
 
-/// Represents the response sent to web clients.
+/// Fields of a `StatisticsEntry` that can be requested with `fields`
+const STATISTICS_FIELDS: [&str; 6] = [
+    "time",
+    "total_count_on_biotools",
+    "total_errors",
+    "unique_tools",
+    "error_types",
+    "severity",
+];
+
+/// A statistics entry without the per code and per severity counts
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct ApiResponse {
-    /// The number of results returned by the query.
-    pub count: i64,
-    /// `null` if there is no next page, otherwise returns `?page={page + 1}`
-    pub next: Option<String>,
-    /// `null` if there is no previous page, otherwise returns `?page={page - 1}`
-    pub previous: Option<String>,
-    /// A list of results matching the query.
-    pub results: Vec<Message>,
+pub struct Snapshot {
+    pub time: u64,
+    pub total_errors: u64,
+    pub unique_tools: u64,
 }
 
-/// Relint parameters
-#[derive(Deserialize, IntoParams)]
-pub struct RelintParams {
-    tool: String,
+/// Backfilled statistics along with the JSON served for them
+#[derive(Clone)]
+pub struct CachedStatistics {
+    /// Modification time of the statistics file when it was read
+    pub modified: Option<SystemTime>,
+    pub statistics: Arc<Statistics>,
+    /// `statistics` serialized once, shared by every full response
+    pub json: Bytes,
 }
 
-/// Serve the main page
+impl CachedStatistics {
+    fn load(path: &std::path::Path) -> Self {
+        let modified = fs::metadata(path).and_then(|x| x.modified()).ok();
+        let statistics = load_statistics(path);
+        let json = serde_json::to_vec(&statistics).unwrap().into();
+
+        Self {
+            modified,
+            statistics: Arc::new(statistics),
+            json,
+        }
+    }
+}
+
+/// Parameters of the statistics endpoint
+#[derive(Deserialize, IntoParams)]
+pub struct StatisticsParams {
+    /// Comma separated fields of each entry to return, e.g. `time,total_errors` (optional, all by default)
+    fields: Option<String>,
+    /// Merge entries into one per day, week or month (optional, raw entries by default)
+    resolution: Option<Resolution>,
+}
+
+/// Bucket size of downsampled statistics
+///
+/// Entries are snapshots, so every bucket holds their average:
+/// - `time` is the start of the bucket (UTC, weeks start on Monday)
+/// - `total_count_on_biotools`, `total_errors` and `unique_tools` are the rounded mean of the bucket
+/// - `error_types` and `severity` are the rounded mean of each key, ignoring entries where it is
+///   `null` or missing, and `null` if no entry has it
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Day,
+    Week,
+    Month,
+}
+
+impl Resolution {
+    /// Unix timestamp of the start of the bucket containing `time`
+    fn bucket(self, time: u64) -> u64 {
+        let date = DateTime::from_timestamp(time as i64, 0)
+            .unwrap_or_default()
+            .date_naive();
+        let start = match self {
+            Resolution::Day => date,
+            Resolution::Week => date - Days::new(date.weekday().num_days_from_monday().into()),
+            Resolution::Month => date.with_day(1).unwrap(),
+        };
+        start.and_time(NaiveTime::MIN).and_utc().timestamp() as u64
+    }
+}
+
+/// Represents the response sent to web clients.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiResponse {
+    /// The number of results returned by the query.
+    pub count: i64,
+    /// `null` if there is no next page, otherwise returns `?page={page + 1}`
+    pub next: Option<String>,
+    /// `null` if there is no previous page, otherwise returns `?page={page - 1}`
+    pub previous: Option<String>,
+    /// Pass as `cursor` to get the next page, `null` if this page is not full
+    pub next_cursor: Option<String>,
+    /// A list of results matching the query.
+    pub results: Vec<Message>,
+}
+
+impl ApiResponse {
+    /// Serialize as XML with the same fields as the JSON, `severity` is the numeric level
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<response>");
+        xml_element(&mut xml, "count", &self.count.to_string());
+        if let Some(next) = &self.next {
+            xml_element(&mut xml, "next", next);
+        }
+        if let Some(previous) = &self.previous {
+            xml_element(&mut xml, "previous", previous);
+        }
+        if let Some(next_cursor) = &self.next_cursor {
+            xml_element(&mut xml, "next_cursor", next_cursor);
+        }
+        xml.push_str("<results>");
+        for message in &self.results {
+            xml.push_str("<message>");
+            xml_element(&mut xml, "time", &message.time.to_string());
+            xml_element(&mut xml, "timestamp", &message.timestamp);
+            xml_element(&mut xml, "tool", &message.tool);
+            xml_element(&mut xml, "tool_url", &message.tool_url);
+            xml_element(&mut xml, "code", &message.code);
+            xml_element(&mut xml, "text", &message.text);
+            if let Some(html) = &message.html {
+                xml_element(&mut xml, "html", html);
+            }
+            xml_element(&mut xml, "severity", &message.level.to_string());
+            xml.push_str("</message>");
+        }
+        xml.push_str("</results></response>\n");
+        xml
+    }
+}
+
+/// Append `<name>value</name>`, escaping the value and dropping characters XML 1.0 does not allow
+fn xml_element(xml: &mut String, name: &str, value: &str) {
+    let value: String = value
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    xml.push_str(&format!(
+        "<{name}>{}</{name}>",
+        quick_xml::escape::escape(&value)
+    ));
+}
+
+/// Whether the client asked for XML with the `Accept` header
+fn accepts_xml(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.contains("application/xml"))
+}
+
+/// Response of a search with `count_only`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchCount {
+    /// The number of messages matching the query.
+    pub count: i64,
+}
+
+/// Versions of the server and of the linter producing the findings
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    /// Version of the server crate
+    pub server: String,
+    /// Commit the server was built from, `null` if it was built outside of a git checkout
+    pub git_hash: Option<String>,
+    /// Version reported by `linter/cli.py --version`, `null` if it could not be run
+    pub linter: Option<String>,
+}
+
+/// Result of one dependency probe of `/healthz`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthProbe {
+    pub ok: bool,
+    /// Why the probe failed
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+}
+
+impl HealthProbe {
+    fn from_result<E: std::fmt::Display>(result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => Self {
+                ok: true,
+                error: None,
+            },
+            Err(e) => Self {
+                ok: false,
+                error: Some(redact_paths(&e.to_string())),
+            },
+        }
+    }
+}
+
+/// Whether the server and the services it depends on work
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Health {
+    /// `ok` if every probe passed, `unhealthy` otherwise
+    pub status: String,
+    /// `SELECT 1` on the database
+    pub database: HealthProbe,
+    /// The statistics file can be opened
+    pub statistics: HealthProbe,
+    /// The linter script and its entry point exist
+    pub linter: HealthProbe,
+}
+
+/// Response of a successful statistics regeneration
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RegenerateResponse {
+    /// Unix timestamp of the newest statistics entry
+    pub time: u64,
+}
+
+/// Number of findings of a tool per severity
+#[derive(Debug, Serialize, Deserialize, ToSchema, Default, Clone, Copy)]
+pub struct SeverityCounts {
+    pub critical: i64,
+    pub high: i64,
+    pub medium: i64,
+    pub low: i64,
+    /// Uncaught linter errors (level 2)
+    pub linter_error: i64,
+    /// Obsolete errors (level 1)
+    pub error: i64,
+    /// Levels not known to this server
+    pub unknown: i64,
+}
+
+/// Weights of each severity used for the tool score
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreWeights {
+    pub critical: f64,
+    pub high: f64,
+    pub medium: f64,
+    pub low: f64,
+    pub linter_error: f64,
+}
+
+/// Default weights, critical findings are security issues so they dominate the score
+/// and linter errors are not the tool's fault so they are not counted
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            critical: 10.0,
+            high: 5.0,
+            medium: 2.0,
+            low: 1.0,
+            linter_error: 0.0,
+        }
+    }
+}
+
+/// Parse weights from `critical=10,high=5,...`, omitted severities keep their default weight
+impl FromStr for ScoreWeights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Self::default();
+        for pair in s.split(',').filter(|x| !x.is_empty()) {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or(format!("Expected severity=weight, got `{pair}`"))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid weight `{value}`"))?;
+            match name.trim() {
+                "critical" => weights.critical = value,
+                "high" => weights.high = value,
+                "medium" => weights.medium = value,
+                "low" => weights.low = value,
+                "linter_error" => weights.linter_error = value,
+                x => return Err(format!("Unknown severity `{x}`")),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+impl ScoreWeights {
+    /// Weighted sum of the findings, lower is better
+    ///
+    /// Obsolete errors are weighted like linter errors.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn score(&self, counts: &SeverityCounts) -> f64 {
+        counts.critical as f64 * self.critical
+            + counts.high as f64 * self.high
+            + counts.medium as f64 * self.medium
+            + counts.low as f64 * self.low
+            + (counts.linter_error + counts.error) as f64 * self.linter_error
+    }
+}
+
+/// Severity-weighted health score of a tool
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolScore {
+    /// The ID of the tool
+    pub tool: String,
+    /// Weighted sum of the findings, `0` means no findings
+    pub score: f64,
+    /// Number of findings per severity the score was computed from
+    pub counts: SeverityCounts,
+}
+
+/// Findings of a tool counted per severity and per error code
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolSummary {
+    /// The ID of the tool
+    pub tool: String,
+    /// Number of findings per severity
+    pub severity: SeverityCounts,
+    /// Number of findings per error code
+    pub codes: BTreeMap<String, i64>,
+    /// Unix timestamp of the last relint through the API, `null` if it was never relinted
+    pub last_checked: Option<i64>,
+}
+
+/// Everything shown on the page of a tool
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolBundle {
+    /// The ID of the tool
+    pub tool: String,
+    /// Number of findings per severity
+    pub severity: SeverityCounts,
+    /// Error codes of the tool, sorted by code
+    pub codes: Vec<ToolCode>,
+    /// Last relint through the API, `null` if it was never relinted
+    pub last_relint: Option<LastRelint>,
+    /// Total number of findings
+    pub count: i64,
+    /// First `TOOL_BUNDLE_MESSAGES` findings sorted by code and time
+    pub results: Vec<Message>,
+}
+
+/// Parameters of the tool findings endpoint
+#[derive(Deserialize, IntoParams)]
+pub struct ToolMessagesParams {
+    /// Format of the findings (optional, default `json`)
+    format: Option<ToolMessagesFormat>,
+}
+
+/// Format of the tool findings endpoint
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolMessagesFormat {
+    /// `ToolMessages`
+    #[default]
+    Json,
+    /// SARIF 2.1.0 log, for GitHub code scanning and other SARIF viewers
+    Sarif,
+}
+
+/// All findings of a tool grouped by error code
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolMessages {
+    pub tool: String,
+    /// Unix timestamp of the newest finding, `null` if the tool has none
+    pub last_linted: Option<i64>,
+    /// Findings of each error code, empty if the tool has none
+    pub codes: BTreeMap<String, CodeMessages>,
+}
+
+/// Findings of a tool with the same error code
+#[derive(Debug, Serialize, Deserialize, ToSchema, Default)]
+pub struct CodeMessages {
+    /// Number of findings with this code
+    pub count: usize,
+    /// Findings sorted by time
+    pub messages: Vec<Message>,
+}
+
+/// Last relint of a tool through the API
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LastRelint {
+    pub tool: String,
+    /// Unix timestamp of the relint
+    pub time: i64,
+    /// A human-readable timestamp formatted as `%Y-%m-%d %H:%M`.
+    pub timestamp: String,
+    /// HTTP status returned by the relint, `200` on success
+    pub status: i32,
+}
+
+/// Details of the last failed relint of a tool
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RelintError {
+    pub tool: String,
+    /// Unix timestamp of the failure
+    pub time: i64,
+    /// A human-readable timestamp formatted as `%Y-%m-%d %H:%M`.
+    pub timestamp: String,
+    /// Exit code of the linter, `null` if it could not be started or was killed
+    pub exit_code: Option<i32>,
+    /// Last lines written to stderr by the linter
+    pub stderr: String,
+}
+
+/// An error code affecting a tool
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolCode {
+    pub code: String,
+    /// Number of findings with this code
+    pub count: i64,
+    /// Most severe finding with this code
+    pub max_severity: Severity,
+}
+
+/// Subscription to new findings of a tool, an error code or both
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WebhookRequest {
+    /// HTTP or HTTPS URL the findings are POSTed to
+    pub url: String,
+    /// biotoolsID of the tool (optional)
+    pub tool: Option<String>,
+    /// Error code (optional)
+    pub code: Option<String>,
+}
+
+/// A registered webhook subscription
+///
+/// New findings are POSTed to `url` as `{subscription, tool, code, messages: [Message]}`, signed
+/// with an `X-Linter-Signature: sha256=<hex>` HMAC-SHA256 of the body keyed with `secret`.
+/// Relints insert the findings of a tool again, so each relint delivers them again.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    /// Normalized biotoolsID, `null` for every tool
+    pub tool: Option<String>,
+    /// Error code, `null` for every code
+    pub code: Option<String>,
+    /// Key of the delivery signatures, only returned on registration
+    pub secret: String,
+}
+
+/// Parameters of the tool list
+#[derive(Deserialize, IntoParams)]
+pub struct ToolsParams {
+    /// The page number (optional, default 0)
+    #[param(minimum = 0)]
+    page: Option<i64>,
+    /// Number of tools per page (1 to 1000, default 100)
+    limit: Option<i64>,
+}
+
+/// Page of tools with findings
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolList {
+    /// Total number of tools with findings
+    pub count: i64,
+    /// `null` if there is no next page, otherwise returns `?page={page + 1}&limit={limit}`
+    pub next: Option<String>,
+    /// `null` if there is no previous page, otherwise returns `?page={page - 1}&limit={limit}`
+    pub previous: Option<String>,
+    /// biotoolsIDs sorted alphabetically
+    pub results: Vec<String>,
+}
+
+/// Parameters of the tool comparison
+#[derive(Deserialize, IntoParams)]
+pub struct CompareParams {
+    /// biotoolsID of the first tool
+    a: String,
+    /// biotoolsID of the second tool
+    b: String,
+}
+
+/// Side by side comparison of two tools
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ToolComparison {
+    pub a: ToolSummary,
+    pub b: ToolSummary,
+    /// Error codes only found in the first tool
+    pub only_a: Vec<String>,
+    /// Error codes only found in the second tool
+    pub only_b: Vec<String>,
+}
+
+/// An error code found in a tool
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+pub struct ToolFinding {
+    pub tool: String,
+    pub code: String,
+}
+
+/// Findings of an external CSV compared with the findings of the linter
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CsvComparison {
+    /// Pairs in the CSV the linter also found
+    pub found: Vec<ToolFinding>,
+    /// Pairs in the CSV the linter did not find
+    pub missed: Vec<ToolFinding>,
+    /// Pairs the linter found in the tools of the CSV that are not in the CSV
+    pub extra: Vec<ToolFinding>,
+}
+
+/// Parameters of the trending tools endpoint
+#[derive(Deserialize, IntoParams)]
+pub struct TrendingParams {
+    /// Size of the compared windows in days (1 to 365, default 7)
+    window: Option<i64>,
+    /// Maximum number of returned tools (1 to 100, default 10)
+    limit: Option<i64>,
+}
+
+/// A tool with findings counted in the last window and in the window before it
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrendingTool {
+    pub tool: String,
+    /// Findings found in the last `window` days
+    pub recent: i64,
+    /// Findings found in the `window` days before that
+    pub prior: i64,
+    /// `recent - prior`
+    pub delta: i64,
+}
+
+/// Result of a single tool in a bulk relint
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkRelintResult {
+    /// The line of the request body
+    pub tool: String,
+    /// HTTP status the single tool relint would have returned
+    pub status: u16,
+    /// Why the tool was not linted, `null` on success
+    pub error: Option<String>,
+}
+
+/// Error returned by every failing `/api/` endpoint
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiError {
+    /// HTTP status code
+    pub status: u16,
+    /// Human readable reason
+    pub message: String,
+    /// ID of the request, the same as the `X-Request-Id` response header, to reference when reporting the error
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub request_id: Option<String>,
+}
+
+/// Body of every failing `/api/` response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiErrorResponse {
+    pub error: ApiError,
+}
+
+/// Parameters of the main page
+#[derive(Deserialize, IntoParams)]
+pub struct IndexParams {
+    /// Search prefilled in the table (optional)
+    q: Option<String>,
+}
+
+/// Relint parameters
+#[derive(Deserialize, IntoParams)]
+pub struct RelintParams {
+    tool: String,
+    /// Return the findings instead of saving them, nothing is recorded (optional, default false)
+    dry_run: Option<bool>,
+}
+
+/// Shown on the main page instead of a statistic that could not be queried
+pub const INDEX_PLACEHOLDER: &str = "—";
+
+/// Longest a single statistic of the main page may take before the page is rendered without it
+const INDEX_COUNT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A statistic of the main page, or `INDEX_PLACEHOLDER` if its query failed or timed out
+pub async fn index_count<T: ToString>(
+    name: &str,
+    query: impl std::future::Future<Output = Result<T, sqlx::Error>>,
+) -> String {
+    index_query(name, query)
+        .await
+        .map_or(INDEX_PLACEHOLDER.to_owned(), |x| x.to_string())
+}
+
+/// Run a main page query with the same timeout as `index_count`, `None` when it fails
+async fn index_query<T>(
+    name: &str,
+    query: impl std::future::Future<Output = Result<T, sqlx::Error>>,
+) -> Option<T> {
+    match tokio::time::timeout(INDEX_COUNT_TIMEOUT, query).await {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            error!("Main page {name} failed: {e}");
+            None
+        }
+        Err(_) => {
+            error!("Main page {name} timed out");
+            None
+        }
+    }
+}
+
+/// Serve the main page
 pub async fn serve_index_page(
     headers: HeaderMap,
+    Query(params): Query<IndexParams>,
+    State(state): State<ServerState>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    info_statement!(sampled state, headers, "WWW-INDEX", "{:?}", params.q);
+
+    // Counted like a search so the same limits apply
+    let search_count = match &params.q {
+        Some(q) => {
+            let query = APIQuery {
+                query: Some(q.clone()),
+                count_only: Some(true),
+                ..Default::default()
+            };
+            Some(search(&state, query).await?.count)
+        }
+        None => None,
+    };
+
+    // Simple statistics, multiple futures executing at once
+    let (error_count, timestamp, tool_count, critical_count, top_codes) = tokio::join!(
+        index_count("error count", db::count_total_messages(&state.pool)),
+        index_count("oldest entry", async {
+            db::get_oldest_entry_unix(&state.pool)
+                .await
+                .map(|x| x.map_or(INDEX_PLACEHOLDER.to_owned(), format_timestamp))
+        }),
+        index_count("tool count", db::count_total_unique_tools(&state.pool)),
+        index_count("critical count", db::count_critical_messages(&state.pool)),
+        index_query("top codes", db::top_codes(&state.pool, INDEX_TOP_CODES)),
+    );
+
+    let mut c = template_context();
+    c.insert("critical_count", &critical_count);
+    c.insert("error_count", &error_count);
+    c.insert("tool_count", &tool_count);
+    c.insert("last_time", &timestamp);
+    c.insert("top_codes", &top_codes.unwrap_or_default());
+    c.insert(
+        "error_codes",
+        &ERROR_CODES.iter().map(|x| x.code).collect::<Vec<_>>(),
+    );
+    c.insert("documentation", &codes::documentation_urls());
+    match (params.q, search_count) {
+        (Some(q), Some(search_count)) => {
+            c.insert("search_count", &search_count);
+            c.insert("search_value", &q);
+        }
+        _ => c.insert("search_value", ""),
+    }
+
+    Ok(Html(TEMPLATES.render("index.html", &c).unwrap()))
+}
+
+/// Serve the stats page
+pub async fn serve_statistics_page(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Html<String> {
+    info_statement!(sampled state, headers, "WWW-STATISTICS", "");
+
+    let c = template_context();
+    Html(TEMPLATES.render("statistics.html", &c).unwrap())
+}
+
+pub async fn serve_documentation_page(
+    headers: HeaderMap,
+    Path(query_title): Path<String>,
+    State(state): State<ServerState>,
+) -> Response {
+    info_statement!(sampled state, headers, "WWW-DOCUMENTATION", "{query_title}");
+
+    // https://stackoverflow.com/questions/56366947/how-does-a-rust-pathbuf-prevent-directory-traversal-attacks
+    let mut p = PathBuf::from_str(&query_title).unwrap();
+    if p.components()
+        .into_iter()
+        .any(|x| x == Component::ParentDir)
+    {
+        let c = template_context();
+        return Html(TEMPLATES.render("error.html", &c).unwrap()).into_response();
+    }
+
+    if p.to_str() == Some("") {
+        p = PathBuf::from_str("index.md").unwrap();
+    }
+
+    let p = p.with_extension("md");
+
+    let markdown_path = PathBuf::from_str("documentation/").unwrap().join(p);
+    serve_markdown(&state, &headers, markdown_path)
+}
+
+pub async fn serve_documentation_index(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Response {
+    info_statement!(sampled state, headers, "WWW-DOCUMENTATION", "");
+
+    let markdown_path = PathBuf::from_str("documentation/index.md").unwrap();
+    serve_markdown(&state, &headers, markdown_path)
+}
+
+/// Render a markdown file into the documentation template
+///
+/// Rendered pages are cached until the file's modification time changes,
+/// and clients that send an up to date `If-Modified-Since` get `304 Not Modified`.
+fn serve_markdown(state: &ServerState, headers: &HeaderMap, markdown_path: PathBuf) -> Response {
+    let modified = fs::metadata(&markdown_path)
+        .and_then(|x| x.modified())
+        .unwrap();
+    let modified_at = DateTime::<Utc>::from(modified);
+    let last_modified = modified_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+    // HTTP dates have a precision of one second
+    let not_modified = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+        .is_some_and(|x| modified_at.timestamp() <= x.timestamp());
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::LAST_MODIFIED, last_modified)],
+        )
+            .into_response();
+    }
+
+    let cached = state
+        .docs_cache
+        .lock()
+        .unwrap()
+        .get(&markdown_path)
+        .filter(|(time, _)| *time == modified)
+        .map(|(_, html)| html.clone());
+
+    let html = match cached {
+        Some(html) => html,
+        None => {
+            let markdown_string = fs::read_to_string(&markdown_path).unwrap();
+            let parser = pulldown_cmark::Parser::new(&markdown_string);
+            let mut html_output = String::new();
+            pulldown_cmark::html::push_html(&mut html_output, parser);
+
+            let mut c = template_context();
+            c.insert("content", &html_output);
+            let html = TEMPLATES.render("documentation.html", &c).unwrap();
+
+            state
+                .docs_cache
+                .lock()
+                .unwrap()
+                .insert(markdown_path, (modified, html.clone()));
+            html
+        }
+    };
+
+    ([(header::LAST_MODIFIED, last_modified)], Html(html)).into_response()
+}
+
+/// Serve statistics JSON data
+#[utoipa::path(
+    get,
+    path = "/api/statistics",
+    operation_id = "getStatistics",
+    responses(
+         (status = 200, description = "Request successful, entries only contain the requested `fields`", body = Statistics,
+         ),
+         (status = 400, description = "Unknown field or resolution"),
+    ),
+    params(StatisticsParams),
+ )]
+pub async fn serve_statistics_api(
+    headers: HeaderMap,
+    Query(params): Query<StatisticsParams>,
+    State(state): State<ServerState>,
+) -> Result<Response, (StatusCode, String)> {
+    info_statement!(
+        sampled state,
+        headers,
+        "API-STATISTICS",
+        "{:?} {:?}",
+        params.fields,
+        params.resolution
+    );
+
+    let fields: Option<Vec<&str>> = params
+        .fields
+        .as_deref()
+        .map(|x| x.split(',').map(str::trim).collect());
+    if let Some(field) = fields
+        .iter()
+        .flatten()
+        .find(|x| !STATISTICS_FIELDS.contains(x))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown statistics field `{field}`"),
+        ));
+    }
+
+    let cached = cached_statistics(&state);
+
+    // The full response is served as is, without serializing it again
+    if fields.is_none() && params.resolution.is_none() {
+        return Ok(([(header::CONTENT_TYPE, "application/json")], cached.json).into_response());
+    }
+
+    let statistics = match params.resolution {
+        Some(resolution) => Cow::Owned(downsample_statistics(&cached.statistics, resolution)),
+        None => Cow::Borrowed(cached.statistics.as_ref()),
+    };
+
+    let Some(fields) = fields else {
+        return Ok(Json(statistics).into_response());
+    };
+
+    // Project each entry to the requested fields
+    let data: Vec<Map<String, Value>> = statistics
+        .data
+        .iter()
+        .map(|entry| {
+            let Value::Object(mut entry) = serde_json::to_value(entry).unwrap() else {
+                unreachable!("Statistics entries serialize to objects")
+            };
+            entry.retain(|key, _| fields.contains(&key.as_str()));
+            entry
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({ "data": data })).into_response())
+}
+
+/// Index of the statistics snapshots, for picking entries before fetching them in full
+#[utoipa::path(
+    get,
+    path = "/api/snapshots",
+    operation_id = "listSnapshots",
+    responses(
+        (status = 200, description = "Every statistics entry, oldest first", body = [Snapshot]),
+    ),
+)]
+pub async fn snapshots_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Json<Vec<Snapshot>> {
+    info_statement!(sampled state, headers, "API-SNAPSHOTS", "");
+
+    let cached = cached_statistics(&state);
+    Json(
+        cached
+            .statistics
+            .data
+            .iter()
+            .map(|x| Snapshot {
+                time: x.time,
+                total_errors: x.total_errors,
+                unique_tools: x.unique_tools,
+            })
+            .collect(),
+    )
+}
+
+/// Farthest a snapshot may be from the requested time, statistics are generated daily
+const SNAPSHOT_TOLERANCE: u64 = 24 * 60 * 60;
+
+/// A single statistics entry, the one nearest to `time`
+#[utoipa::path(
+    get,
+    path = "/api/statistics/{time}",
+    operation_id = "getStatisticsSnapshot",
+    params(
+        ("time" = u64, Path, description = "Unix timestamp of the snapshot"),
+    ),
+    responses(
+        (status = 200, description = "The entry nearest to `time`", body = StatisticsEntry),
+        (status = 404, description = "No entry within a day of `time`"),
+    ),
+)]
+pub async fn statistics_snapshot_api(
+    headers: HeaderMap,
+    Path(time): Path<u64>,
+    State(state): State<ServerState>,
+) -> Result<Json<StatisticsEntry>, StatusCode> {
+    info_statement!(sampled state, headers, "API-STATISTICS-SNAPSHOT", "{}", time);
+
+    let cached = cached_statistics(&state);
+    cached
+        .statistics
+        .data
+        .iter()
+        .min_by_key(|x| x.time.abs_diff(time))
+        .filter(|x| x.time.abs_diff(time) <= SNAPSHOT_TOLERANCE)
+        .map(|x| Json(x.clone()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Statistics of the cache, reloaded when the statistics file was modified since it was read
+fn cached_statistics(state: &ServerState) -> CachedStatistics {
+    let modified = fs::metadata(&state.stats_file_path)
+        .and_then(|x| x.modified())
+        .ok();
+
+    if let Some(cached) = state.statistics.read().unwrap().as_ref() {
+        if cached.modified == modified {
+            return cached.clone();
+        }
+    }
+
+    let cached = CachedStatistics::load(&state.stats_file_path);
+    *state.statistics.write().unwrap() = Some(cached.clone());
+    cached
+}
+
+/// Read the statistics file and make entries have all error types even if they will be null
+///
+/// The error types are `ERROR_CODES` and any other code found in the file, so codes added to
+/// the linter are backfilled without updating the server.
+pub fn load_statistics(path: &std::path::Path) -> Statistics {
+    let json_str = fs::read_to_string(path).expect("Should have been able to read json file");
+
+    let mut json: Statistics = serde_json::from_str(&json_str).expect("Could not parse JSON");
+
+    let mut codes: Vec<String> = ERROR_CODES.iter().map(|x| x.code.to_owned()).collect();
+    for entry in &json.data {
+        for code in entry.error_types.keys() {
+            if !codes.contains(code) {
+                codes.push(code.clone());
+            }
+        }
+    }
+
+    for entry in &mut json.data {
+        for code in &codes {
+            if !entry.error_types.contains_key(code) {
+                entry.error_types.insert(code.clone(), Value::Null);
+            }
+        }
+    }
+
+    json
+}
+
+/// Average the entries falling into the same bucket, see `Resolution`
+fn downsample_statistics(statistics: &Statistics, resolution: Resolution) -> Statistics {
+    let mut buckets: BTreeMap<u64, Vec<&StatisticsEntry>> = BTreeMap::new();
+    for entry in &statistics.data {
+        buckets
+            .entry(resolution.bucket(entry.time))
+            .or_default()
+            .push(entry);
+    }
+
+    let mean = |values: Vec<u64>| {
+        let len = values.len() as u64;
+        (values.iter().sum::<u64>() + len / 2) / len
+    };
+    let mean_map = |maps: Vec<&Map<String, Value>>| {
+        let mut keys: Vec<&String> = maps.iter().flat_map(|x| x.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .map(|key| {
+                let values: Vec<u64> = maps
+                    .iter()
+                    .filter_map(|x| x.get(key).and_then(Value::as_u64))
+                    .collect();
+                let value = match values.is_empty() {
+                    true => Value::Null,
+                    false => mean(values).into(),
+                };
+                (key.clone(), value)
+            })
+            .collect::<Map<String, Value>>()
+    };
+
+    let data = buckets
+        .into_iter()
+        .map(|(time, entries)| {
+            let severities: Vec<&Map<String, Value>> =
+                entries.iter().filter_map(|x| x.severity.as_ref()).collect();
+            StatisticsEntry {
+                time,
+                total_count_on_biotools: mean(
+                    entries.iter().map(|x| x.total_count_on_biotools).collect(),
+                ),
+                total_errors: mean(entries.iter().map(|x| x.total_errors).collect()),
+                unique_tools: mean(entries.iter().map(|x| x.unique_tools).collect()),
+                error_types: mean_map(entries.iter().map(|x| &x.error_types).collect()),
+                severity: match severities.is_empty() {
+                    true => None,
+                    false => Some(mean_map(severities)),
+                },
+            }
+        })
+        .collect();
+
+    Statistics { data }
+}
+
+/// Reject requests without the configured API key in the `X-API-Key` header
+pub async fn require_api_key<B>(
+    State(state): State<ServerState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    let provided = request
+        .headers()
+        .get("X-API-Key")
+        .and_then(|x| x.to_str().ok());
+
+    match (&state.config.api_key, provided) {
+        (Some(key), Some(provided)) if api_key_matches(key, provided) => {
+            Ok(next.run(request).await)
+        }
+        _ => {
+            info!("Request without a valid API key, aborting");
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+/// Compare keys in constant time, hashing first so the time doesn't depend on their lengths either
+fn api_key_matches(key: &str, provided: &str) -> bool {
+    Sha256::digest(key.as_bytes())
+        .ct_eq(&Sha256::digest(provided.as_bytes()))
+        .into()
+}
+
+/// Set `X-Real-IP` to the peer address when no proxy did, so logs and rate limits see the client
+pub async fn peer_ip_fallback<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    if !request.headers().contains_key("X-Real-IP") {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|x| x.0.ip().to_string());
+        if let Some(value) = peer.and_then(|x| HeaderValue::from_str(&x).ok()) {
+            request.headers_mut().insert("X-Real-IP", value);
+        }
+    }
+    next.run(request).await
+}
+
+/// Give every request an ID, sent back in the `X-Request-Id` header and included in its log lines
+///
+/// A client's own `X-Request-Id` is kept if it is a short token, so requests can be correlated
+/// across services. Otherwise a random ID is generated.
+pub async fn request_id<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let client_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|x| x.to_str().ok())
+        .filter(|x| {
+            !x.is_empty()
+                && x.len() <= MAX_REQUEST_ID_LENGTH
+                && x.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        })
+        .map(str::to_owned);
+    let id = client_id.unwrap_or_else(|| format!("{:032x}", rand::random::<u128>()));
+    // Only the characters above are allowed, so the ID is always a valid header value
+    let value = HeaderValue::from_str(&id).unwrap();
+
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, value.clone());
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+/// Log every failed request, including the read requests skipped by sampling
+pub async fn log_failed_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_owned();
+    let header = |name| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|x: &HeaderValue| x.to_str().ok())
+            .unwrap_or("?")
+            .to_owned()
+    };
+    let ip = header("X-Real-IP");
+    let request_id = header(REQUEST_ID_HEADER);
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        info!("HTTP {method} {path} FAILED {status} FROM IP `{ip}` REQUEST `{request_id}`");
+    }
+    response
+}
+
+/// Replace the body of failed `/api/` responses with an `ApiErrorResponse`, keeping the status
+pub async fn json_api_errors<B>(request: Request<B>, next: Next<B>) -> Response {
+    let is_api = request.uri().path().starts_with("/api/");
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|x| x.to_str().ok())
+        .map(str::to_owned);
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if !is_api || !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    // Reuse the plain text reason of the handler if there is one
+    let (_, mut body) = response.into_parts();
+    let mut bytes = Vec::new();
+    while let Some(Ok(chunk)) = body.data().await {
+        bytes.extend_from_slice(&chunk);
+    }
+    let message = match String::from_utf8(bytes) {
+        Ok(text) if !text.is_empty() => text,
+        _ => status.canonical_reason().unwrap_or("Error").to_owned(),
+    };
+
+    (
+        status,
+        Json(ApiErrorResponse {
+            error: ApiError {
+                status: status.as_u16(),
+                message,
+                request_id,
+            },
+        }),
+    )
+        .into_response()
+}
+
+/// Regenerate statistics with linter/statistics.py and reload them
+#[utoipa::path(
+    post,
+    path = "/api/admin/regenerate-stats",
+    operation_id = "regenerateStatistics",
+    responses(
+        (status = 200, description = "Statistics regenerated", body = RegenerateResponse),
+        (status = 401, description = "Missing or invalid `X-API-Key` header"),
+        (status = 500, description = "Statistics script failed"),
+    ),
+)]
+pub async fn regenerate_statistics_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Result<Json<RegenerateResponse>, StatusCode> {
+    info_statement!(headers, "API-REGENERATE-STATS", "");
+
+    // The script runs from the repository root, so relative paths have to be resolved first
+    let stats_file_path = fs::canonicalize(&state.stats_file_path).map_err(|e| {
+        error!("Could not resolve statistics file: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let script = "stats_from_server.sh";
+
+    let output = Command::new("bash")
+        .arg(script)
+        .arg(stats_file_path)
+        .current_dir(LINTER_DIR)
+        .output();
+
+    debug!("Output from script: {:?}", output);
+
+    match output {
+        Ok(output) if output.status.success() => {
+            info!("Output from script: {}", redacted_output(&output));
+        }
+        Ok(output) => {
+            error!("Statistics script failed: {}", redacted_output(&output));
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Err(e) => {
+            error!(
+                "Could not run the statistics script: {}",
+                redact_paths(&e.to_string())
+            );
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let cached = CachedStatistics::load(&state.stats_file_path);
+    let time = cached.statistics.data.last().map_or(0, |x| x.time);
+    *state.statistics.write().unwrap() = Some(cached);
+
+    // The linter may have been updated together with the statistics
+    refresh_linter_version(&state).await;
+
+    Ok(Json(RegenerateResponse { time }))
+}
+
+/// Versions of the server and the linter
+///
+/// The linter version is read on startup and again when the statistics are regenerated.
+#[utoipa::path(
+    get,
+    path = "/api/version",
+    operation_id = "getVersion",
+    responses(
+        (status = 200, description = "Server and linter versions", body = VersionInfo),
+    ),
+)]
+pub async fn version_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Json<VersionInfo> {
+    info_statement!(sampled state, headers, "API-VERSION", "");
+
+    Json(VersionInfo {
+        server: env!("CARGO_PKG_VERSION").to_owned(),
+        git_hash: option_env!("GIT_HASH").map(str::to_owned),
+        linter: state.linter_version.read().unwrap().clone(),
+    })
+}
+
+/// Health of the server and its dependencies, for load balancers and watchdogs
+///
+/// Probes the database, the statistics file and the linter script. Responds with `503` when any of
+/// them fails, see `/livez` for a check that only the server process is up.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    operation_id = "getHealth",
+    responses(
+        (status = 200, description = "Every probe passed", body = Health),
+        (status = 503, description = "At least one probe failed, see `error` of the failed probes", body = Health),
+    ),
+)]
+pub async fn health_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> (StatusCode, Json<Health>) {
+    info_statement!(sampled state, headers, "API-HEALTH", "");
+
+    let database = match tokio::time::timeout(HEALTH_TIMEOUT, db::ping(&state.pool)).await {
+        Ok(result) => HealthProbe::from_result(result),
+        Err(_) => HealthProbe::from_result(Err("timed out")),
+    };
+    let statistics = HealthProbe::from_result(fs::File::open(&state.stats_file_path).map(|_| ()));
+    let linter = HealthProbe::from_result(
+        ["lint_from_server.sh", "linter/cli.py"]
+            .iter()
+            .try_for_each(|x| fs::metadata(PathBuf::from(LINTER_DIR).join(x)).map(|_| ())),
+    );
+
+    let ok = database.ok && statistics.ok && linter.ok;
+    let health = Health {
+        status: if ok { "ok" } else { "unhealthy" }.to_owned(),
+        database,
+        statistics,
+        linter,
+    };
+    match ok {
+        true => (StatusCode::OK, Json(health)),
+        false => (StatusCode::SERVICE_UNAVAILABLE, Json(health)),
+    }
+}
+
+/// Responds as long as the server process serves requests, without touching any dependency
+#[utoipa::path(
+    get,
+    path = "/livez",
+    operation_id = "getLiveness",
+    responses(
+        (status = 200, description = "The server is up", content_type = "text/plain"),
+    ),
+)]
+pub async fn liveness_api() -> &'static str {
+    "ok"
+}
+
+/// Ask the linter for its version again and keep it in the state
+pub async fn refresh_linter_version(state: &ServerState) {
+    let version = tokio::task::spawn_blocking(linter_version)
+        .await
+        .unwrap_or_default();
+    *state.linter_version.write().unwrap() = version;
+}
+
+/// Ask the linter for its version, blocks until it answers
+pub fn linter_version() -> Option<String> {
+    let output = Command::new("bash")
+        .arg("lint_from_server.sh")
+        .arg("--version")
+        .current_dir(LINTER_DIR)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+                .filter(|x| !x.is_empty())
+        }
+        Ok(output) => {
+            error!(
+                "Could not read the linter version: {}",
+                redacted_output(&output)
+            );
+            None
+        }
+        Err(e) => {
+            error!("Could not run the linter: {}", redact_paths(&e.to_string()));
+            None
+        }
+    }
+}
+
+/// List every error or search for a specific one
+#[utoipa::path(
+   get,
+   path = "/api/search",
+   operation_id = "searchMessages",
+   responses(
+        (status = 200, description = "Search successful, `SearchCount` with `count_only`", body = ApiResponse,
+            content(
+                ("application/json" = ApiResponse),
+                ("application/xml" = ApiResponse),
+            ),
+        ),
+        (status = 400, description = "Page is past the maximum search offset or query is too long"),
+   ),
+   params(
+    APIQuery
+),
+)]
+pub async fn serve_search_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Query(params): Query<APIQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    info_statement!(
+        sampled state,
+        headers,
+        "API-SEARCH",
+        "{:?}, {:?}, {:?}",
+        params.query,
+        params.page,
+        params.severity
+    );
+
+    let count_only = params.count_only.unwrap_or(false);
+    let response = search(&state, params).await?;
+
+    Ok(match count_only {
+        true => Json(SearchCount {
+            count: response.count,
+        })
+        .into_response(),
+        false if accepts_xml(&headers) => (
+            [(header::CONTENT_TYPE, "application/xml")],
+            response.to_xml(),
+        )
+            .into_response(),
+        false => Json(response).into_response(),
+    })
+}
+
+/// Search with the filters sent as a JSON body, same as `GET /api/search`
+#[utoipa::path(
+   post,
+   path = "/api/search",
+   operation_id = "searchMessagesPost",
+   request_body = APIQuery,
+   responses(
+        (status = 200, description = "Search successful, `SearchCount` with `count_only`", body = ApiResponse),
+        (status = 400, description = "Page is past the maximum search offset or query is too long"),
+   ),
+)]
+pub async fn serve_search_post_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Json(params): Json<APIQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    info_statement!(
+        sampled state,
+        headers,
+        "API-SEARCH-POST",
+        "{:?}, {:?}, {:?}",
+        params.query,
+        params.page,
+        params.severity
+    );
+
+    let count_only = params.count_only.unwrap_or(false);
+    let response = search(&state, params).await?;
+
+    Ok(match count_only {
+        true => Json(SearchCount {
+            count: response.count,
+        })
+        .into_response(),
+        false => Json(response).into_response(),
+    })
+}
+
+/// Number of messages matching a search, sent in the `X-Total-Count` header without a body
+///
+/// Takes the same parameters as `GET /api/search`, only the count query is run.
+#[utoipa::path(
+   head,
+   path = "/api/search",
+   operation_id = "countMessages",
+   params(APIQuery),
+   responses(
+        (status = 200, description = "Number of matching messages in the `X-Total-Count` header"),
+        (status = 400, description = "Page is past the maximum search offset or query is too long"),
+   ),
+)]
+pub async fn serve_search_head(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Query(mut params): Query<APIQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    info_statement!(
+        sampled state,
+        headers,
+        "API-SEARCH-HEAD",
+        "{:?}, {:?}, {:?}",
+        params.query,
+        params.page,
+        params.severity
+    );
+
+    params.count_only = Some(true);
+    let response = search(&state, params).await?;
+
+    Ok([("X-Total-Count", response.count.to_string())].into_response())
+}
+
+/// Rendered table rows of a search page, for appending to the table without reloading
+///
+/// Takes the same parameters as `GET /api/search`, the next page is sent in the `X-Next-Page` header.
+#[utoipa::path(
+   get,
+   path = "/api/search/partial",
+   operation_id = "searchMessagesPartial",
+   params(APIQuery),
+   responses(
+        (status = 200, description = "HTML `<tr>` rows", content_type = "text/html"),
+   ),
+)]
+pub async fn serve_search_partial(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Query(mut params): Query<APIQuery>,
+) -> Response {
+    info_statement!(
+        sampled state,
+        headers,
+        "API-SEARCH-PARTIAL",
+        "{:?}, {:?}, {:?}",
+        params.query,
+        params.page,
+        params.severity
+    );
+
+    // The template escapes the values itself
+    params.raw = Some(true);
+    let mut response = match search(&state, params).await {
+        Ok(response) => response,
+        Err(e) => return e.into_response(),
+    };
+    for message in &mut response.results {
+        message.autolink();
+    }
+
+    let mut c = Context::new();
+    c.insert("messages", &response.results);
+    c.insert("documentation", &codes::documentation_urls());
+    let html = Html(TEMPLATES.render("results_rows.html", &c).unwrap());
+
+    match response.next {
+        Some(next) => ([("X-Next-Page", next)], html).into_response(),
+        None => html.into_response(),
+    }
+}
+
+/// Search logic shared by the GET and POST search endpoints
+async fn search(
+    state: &ServerState,
+    params: APIQuery,
+) -> Result<ApiResponse, (StatusCode, String)> {
+    // Get parameters
+    let query = params.query.map(|x| x.trim().to_owned());
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(SearchCursor::from_str)
+        .transpose()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid cursor".to_owned()))?;
+    // A cursor continues where its page ended, it has no offset
+    let page = match cursor {
+        Some(_) => 0,
+        None => params.page.unwrap_or(0),
+    };
+
+    let max_length = state.config.max_query_length;
+    if query
+        .as_ref()
+        .is_some_and(|x| x.chars().count() > max_length)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Queries are limited to {max_length} characters"),
+        ));
+    }
+
+    if page < 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Pages are numbered from 0".to_owned(),
+        ));
+    }
+    // Large offsets make Postgres scan and discard every skipped row
+    let max_offset = state.config.max_search_offset;
+    let Some(offset) = page.checked_mul(100).filter(|&x| x <= max_offset) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Pages past offset {max_offset} are not available, narrow the search with query, code or severity instead"
+            ),
+        ));
+    };
+    let field = params.field.unwrap_or_default();
+    let severity = params.severity.unwrap_or_default();
+    let code = params
+        .code
+        .as_deref()
+        .map(CodeFilter::parse)
+        .unwrap_or_default();
+    let time = TimeRange::new(params.since, params.until);
+
+    let count_only = params.count_only.unwrap_or(false);
+    let sort = params.sort.unwrap_or_default();
+    let order = SearchOrder {
+        sort,
+        direction: params.order.unwrap_or(sort.default_direction()),
+    };
+    // Cursors are positions in the default order
+    let keyset = order == SearchOrder::default();
+    if cursor.is_some() && !keyset {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Cursors are only available with the default sort".to_owned(),
+        ));
+    }
+    let position = SearchPage {
+        start: match cursor {
+            Some(cursor) => SearchStart::After(cursor),
+            None => SearchStart::Offset(offset),
+        },
+        order,
+    };
+
+    let ((mut messages, next_cursor), total_count) = match query {
+        None => {
+            join!(
+                async {
+                    if count_only {
+                        return (Vec::new(), None);
+                    }
+                    db::get_messages_paginated(&state.pool, position, &severity, &code, time).await
+                },
+                db::count_messages_paginated(&state.pool, &severity, &code, time)
+            )
+        }
+        Some(query) => {
+            join!(
+                async {
+                    if count_only {
+                        return (Vec::new(), None);
+                    }
+                    db::get_messages_paginated_search(
+                        &state.pool,
+                        position,
+                        &query,
+                        field,
+                        &severity,
+                        &code,
+                        time,
+                    )
+                    .await
+                },
+                db::count_messages_paginated_search(
+                    &state.pool,
+                    &query,
+                    field,
+                    &severity,
+                    &code,
+                    time
+                )
+            )
+        }
+    };
+
+    for message in &mut messages {
+        message.link_tool(&state.config.tool_url_base);
+    }
+    if !params.raw.unwrap_or(false) {
+        for message in &mut messages {
+            message.escape();
+            message.autolink();
+        }
+    }
+
+    // Page links don't apply to a cursor, its position is unknown
+    Ok(ApiResponse {
+        count: total_count,
+        next: if cursor.is_none() && offset + 100 < total_count && offset + 100 <= max_offset {
+            Some(format!("?page={}", page + 1))
+        } else {
+            None
+        },
+        previous: if page > 0 {
+            Some(format!("?page={}", page - 1))
+        } else {
+            None
+        },
+        next_cursor: next_cursor.filter(|_| keyset).map(|x| x.to_string()),
+        results: messages,
+    })
+}
+
+/// Queue a relint of a specific tool, poll `GET /api/lint/status/{job_id}` for its progress
+///
+/// With `dry_run` the linter runs right away and the findings are returned instead.
+#[utoipa::path(
+    post,
+    path = "/api/lint",
+    operation_id = "relintTool",
+    params(RelintParams),
+    responses(
+        (status = 202, description = "Relint queued", body = LintJob),
+        (status = 200, description = "With `dry_run` the findings of the tool", body = [Message]),
+        (status = 404, description = "With `dry_run`, tool not found on bio.tools"),
+        (status = 409, description = "This tool is already being linted"),
+        (status = 429, description = "This IP is already linting"),
+        (status = 500, description = "Invalid biotoolsID or linter error"),
+    ),
+)]
+pub async fn relint_api(
+    headers: HeaderMap,
+    socket_addr: ConnectInfo<SocketAddr>,
+    Query(params): Query<RelintParams>,
+    State(state): State<ServerState>,
+) -> Response {
+    let input = normalize_tool_id(&params.tool);
+    info_statement!(headers, "API-RELINT", "{}", input);
+
+    let ip = client_ip(&headers, &socket_addr);
+    let dry_run = params.dry_run.unwrap_or(false);
+
+    // The lock is released before awaiting
+    {
+        let mut ips = state.ips.lock().unwrap();
+
+        if ips.contains_key(&ip) {
+            info!("IP is already linting, aborting");
+            return StatusCode::TOO_MANY_REQUESTS.into_response();
+        }
+        if ips.values().any(|v| *v == input) {
+            info!("Tool is already being linted, aborting");
+            return StatusCode::CONFLICT.into_response();
+        }
+
+        // Escape injection attacks
+        if !BIOTOOLS_ID_REGEX.is_match(&input) {
+            info!("Input did not pass regex, aborting");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+
+        if input.contains("--lint-all") {
+            info!("Input contains -lint-all, aborting");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+
+        // Insert IP and tool into server state, queued relints take a slot once a worker runs them
+        if dry_run {
+            ips.insert(ip.clone(), input.clone());
+        }
+    }
+
+    if dry_run {
+        // The linter runs on a blocking thread, the slot is freed even if that thread panics
+        let tool = input.clone();
+        let result = tokio::task::spawn_blocking(move || preview_tool(&tool))
+            .await
+            .unwrap_or_else(|e| {
+                Err(LintFailure {
+                    exit_code: None,
+                    stderr: e.to_string(),
+                })
+            });
+        state.ips.lock().unwrap().remove(&ip);
+
+        return match result {
+            Ok((StatusCode::NOT_FOUND, _)) => {
+                (StatusCode::NOT_FOUND, "Tool not found on bio.tools").into_response()
+            }
+            Ok((status, mut messages)) => {
+                for message in &mut messages {
+                    message.link_tool(&state.config.tool_url_base);
+                    message.escape();
+                    message.autolink();
+                }
+                (status, Json(messages)).into_response()
+            }
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+    }
+
+    match jobs::enqueue(&state, &input, &ip).await {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(EnqueueError::ToolPending) => {
+            info!("Tool is already queued, aborting");
+            StatusCode::CONFLICT.into_response()
+        }
+        Err(EnqueueError::IpPending) => {
+            info!("IP already has a queued relint, aborting");
+            StatusCode::TOO_MANY_REQUESTS.into_response()
+        }
+        Err(EnqueueError::Database(e)) => {
+            error!("Could not queue a relint: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lines written by the linter of a queued relint, as Server-Sent Events while it runs
+///
+/// Each line is a `message` event, lines written before connecting are sent first. A `done`
+/// event with the `LintJob` ends the stream once the relint finished or failed.
+#[utoipa::path(
+    get,
+    path = "/api/lint/stream/{job_id}",
+    operation_id = "streamLintOutput",
+    params(("job_id" = i32, Path, description = "ID returned when the relint was queued")),
+    responses(
+        (status = 200, description = "Event stream of the linter output", content_type = "text/event-stream"),
+        (status = 404, description = "No such job"),
+    ),
+)]
+pub async fn lint_stream_api(
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+    State(state): State<ServerState>,
+) -> Result<Response, StatusCode> {
+    info_statement!(sampled state, headers, "API-LINT-STREAM", "{}", id);
+
+    if db::lint_job(&state.pool, id).await.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let (mut sender, response_body) = axum::body::Body::channel();
+
+    tokio::spawn(async move {
+        // Wait for a worker to start the job, the comments keep proxies from closing the stream
+        let output = loop {
+            if let Some(output) = state.lint_output.lock().unwrap().get(&id).cloned() {
+                break Some(output);
+            }
+            match db::lint_job(&state.pool, id).await {
+                Some(job) if job.status == "queued" || job.status == "running" => {
+                    if sender.send_data(": queued\n\n".into()).await.is_err() {
+                        return;
+                    }
+                    tokio::time::sleep(jobs::JOB_POLL_INTERVAL).await;
+                }
+                _ => break None,
+            }
+        };
+
+        if let Some(output) = output {
+            // Only the worker may keep the output alive, dropping it ends the receiver
+            let (lines, mut receiver) = output.subscribe();
+            drop(output);
+
+            for line in lines {
+                if sender
+                    .send_data(sse_event("message", &line).into())
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            loop {
+                let line = match receiver.recv().await {
+                    Ok(line) => line,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if sender
+                    .send_data(sse_event("message", &line).into())
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+
+        if let Some(job) = db::lint_job(&state.pool, id).await {
+            let json = serde_json::to_string(&job).unwrap();
+            let _ = sender.send_data(sse_event("done", &json).into()).await;
+        }
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        axum::body::boxed(response_body),
+    )
+        .into_response())
+}
+
+/// A Server-Sent Event with a single line of data, line breaks would end the data early
+fn sse_event(event: &str, data: &str) -> String {
+    format!(
+        "event: {event}\ndata: {}\n\n",
+        data.replace(['\r', '\n'], "")
+    )
+}
+
+/// Progress of a relint queued with `POST /api/lint`
+#[utoipa::path(
+    get,
+    path = "/api/lint/status/{job_id}",
+    operation_id = "getLintStatus",
+    params(("job_id" = i32, Path, description = "ID returned when the relint was queued")),
+    responses(
+        (status = 200, description = "The job, `result` is set once it finished or failed", body = LintJob),
+        (status = 404, description = "No such job"),
+    ),
+)]
+pub async fn lint_status_api(
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+    State(state): State<ServerState>,
+) -> Result<Json<LintJob>, StatusCode> {
+    info_statement!(sampled state, headers, "API-LINT-STATUS", "{}", id);
+
+    db::lint_job(&state.pool, id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Check the URLs of a bio.tools entry without running the linter
+///
+/// Reports the same `URL_*` findings as a relint, with `location` being the path of the URL in the entry.
+/// Nothing is stored, the entry may be a draft that is not on bio.tools yet.
+#[utoipa::path(
+    post,
+    path = "/api/lint/urls",
+    operation_id = "lintUrls",
+    request_body(content = Object, description = "bio.tools entry as returned by the bio.tools API"),
+    responses(
+        (status = 200, description = "Findings of the URLs in the entry", body = [Finding]),
+        (status = 401, description = "Missing or invalid `X-API-Key` header"),
+    ),
+)]
+pub async fn lint_urls_api(headers: HeaderMap, Json(entry): Json<Value>) -> Json<Vec<Finding>> {
+    info_statement!(
+        headers,
+        "API-LINT-URLS",
+        "{}",
+        entry["biotoolsID"].as_str().unwrap_or_default()
+    );
+
+    Json(urls::check_entry(&entry).await)
+}
+
+/// Check the publications of a bio.tools entry for missing DOIs, PMIDs and PMCIDs without running the linter
+///
+/// The IDs are looked up with the NCBI ID converter, `location` is the path of the publication in the entry.
+#[utoipa::path(
+    post,
+    path = "/api/lint/publications",
+    operation_id = "lintPublications",
+    request_body(content = Object, description = "bio.tools entry as returned by the bio.tools API"),
+    responses(
+        (status = 200, description = "Findings of the publications in the entry", body = [Finding]),
+        (status = 401, description = "Missing or invalid `X-API-Key` header"),
+    ),
+)]
+pub async fn lint_publications_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Json(entry): Json<Value>,
+) -> Json<Vec<Finding>> {
+    info_statement!(
+        headers,
+        "API-LINT-PUBLICATIONS",
+        "{}",
+        entry["biotoolsID"].as_str().unwrap_or_default()
+    );
+
+    Json(publications::check_entry(&state.config.idconv_url, &entry).await)
+}
+
+/// Check EDAM terms for being obsolete, not recommended or not in the ontology
+///
+/// Terms are class IDs like `http://edamontology.org/operation_0324` or their last part like `operation_0324`.
+/// The `location` of a finding is the index of the term in the body.
+#[utoipa::path(
+    post,
+    path = "/api/edam/validate",
+    operation_id = "validateEdam",
+    request_body(content = Vec<String>, description = "EDAM terms"),
+    responses(
+        (status = 200, description = "Findings of the terms, empty if all of them are valid", body = [Finding]),
+        (status = 503, description = "The EDAM ontology is not loaded"),
+    ),
+)]
+pub async fn edam_validate_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Json(terms): Json<Vec<String>>,
+) -> Result<Json<Vec<Finding>>, (StatusCode, String)> {
+    info_statement!(sampled state, headers, "API-EDAM-VALIDATE", "{}", terms.len());
+
+    let Some(edam) = &state.edam else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The EDAM ontology is not loaded".to_owned(),
+        ));
+    };
+    Ok(Json(
+        terms
+            .iter()
+            .enumerate()
+            .filter_map(|(index, term)| edam.check(&index.to_string(), term.trim()))
+            .collect(),
+    ))
+}
+
+/// Relint every tool in a newline separated body, streaming one JSON result per line as each tool completes
+///
+/// Invalid IDs are reported and skipped, the rest of the batch continues.
+#[utoipa::path(
+    post,
+    path = "/api/lint/bulk",
+    operation_id = "relintToolsBulk",
+    request_body(content = String, description = "biotoolsIDs, one per line", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "Newline delimited JSON, one result per tool", body = BulkRelintResult, content_type = "application/x-ndjson"),
+        (status = 429, description = "This IP is already linting"),
+    ),
+)]
+pub async fn bulk_relint_api(
+    headers: HeaderMap,
+    socket_addr: ConnectInfo<SocketAddr>,
+    State(state): State<ServerState>,
+    body: String,
+) -> Result<Response, StatusCode> {
+    let ip = client_ip(&headers, &socket_addr);
+    info_statement!(headers, "API-RELINT-BULK", "{} lines", body.lines().count());
+
+    {
+        let mut ips = state.ips.lock().unwrap();
+        if ips.contains_key(&ip) {
+            info!("IP is already linting, aborting");
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+        ips.insert(ip.clone(), String::new());
+    }
+
+    let tools: Vec<String> = body
+        .lines()
+        .map(normalize_tool_id)
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    let (mut sender, response_body) = axum::body::Body::channel();
+
+    tokio::spawn(async move {
+        for tool in tools {
+            let result = bulk_relint_tool(&state, &ip, tool).await;
+            let mut line = serde_json::to_string(&result).unwrap();
+            line.push('\n');
+
+            // Client disconnected, don't lint the rest
+            if sender.send_data(line.into()).await.is_err() {
+                break;
+            }
+        }
+
+        state.ips.lock().unwrap().remove(&ip);
+    });
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::boxed(response_body),
+    )
+        .into_response())
+}
+
+/// Relint a single tool of a bulk or scheduled relint, the IP slot is held by the caller
+pub async fn bulk_relint_tool(state: &ServerState, ip: &str, tool: String) -> BulkRelintResult {
+    // Escape injection attacks
+    if !BIOTOOLS_ID_REGEX.is_match(&tool) || tool.contains("--lint-all") {
+        return BulkRelintResult {
+            tool,
+            status: StatusCode::BAD_REQUEST.as_u16(),
+            error: Some("Invalid biotoolsID".to_owned()),
+        };
+    }
+
+    {
+        let mut ips = state.ips.lock().unwrap();
+        if ips.values().any(|v| *v == tool) {
+            return BulkRelintResult {
+                tool,
+                status: StatusCode::CONFLICT.as_u16(),
+                error: Some("Tool is already being linted".to_owned()),
+            };
+        }
+        ips.insert(ip.to_owned(), tool.clone());
+    }
+
+    let status = run_relint(state, tool.clone(), |_| {}).await;
+
+    BulkRelintResult {
+        tool,
+        status: status.as_u16(),
+        error: match status {
+            StatusCode::OK => None,
+            StatusCode::NOT_FOUND => Some("Tool not found on bio.tools".to_owned()),
+            _ => Some("Linter failed".to_owned()),
+        },
+    }
+}
+
+/// Relint a validated biotoolsID without blocking the runtime and record the result
+///
+/// The caller holds the relint slot of the tool, `on_line` gets each line the linter writes.
+/// Waits for one of the `--lint-workers` linters to be free, whether the relint is queued, bulk or scheduled.
+#[tracing::instrument(name = "relint", skip(state, on_line))]
+pub async fn run_relint(
+    state: &ServerState,
+    tool: String,
+    on_line: impl Fn(&str) + Send + Sync + 'static,
+) -> StatusCode {
+    // The semaphore is never closed
+    let _permit = state.linters.acquire().await.unwrap();
+    let input = tool.clone();
+    // The linter span is a child of this one even though it runs on a blocking thread
+    let span = tracing::Span::current();
+    let result = tokio::task::spawn_blocking(move || span.in_scope(|| lint_tool(&input, &on_line)))
+        .await
+        .unwrap_or_else(|e| {
+            Err(LintFailure {
+                exit_code: None,
+                stderr: e.to_string(),
+            })
+        });
+    record_lint_result(state, &tool, result).await
+}
+
+/// A linter run that did not finish with a known exit code
+struct LintFailure {
+    exit_code: Option<i32>,
+    stderr: String,
+}
+
+/// Record a relint and the details of its failure, returns the status for the client
+async fn record_lint_result(
+    state: &ServerState,
+    tool: &str,
+    result: Result<StatusCode, LintFailure>,
+) -> StatusCode {
+    let status = match result {
+        Ok(status) => status,
+        Err(failure) => {
+            if let Err(e) = db::record_relint_error(
+                &state.pool,
+                tool,
+                failure.exit_code,
+                &failure.stderr,
+                state.clock.now(),
+                state.config.relint_error_ttl,
+            )
+            .await
+            {
+                error!("Could not record the linter failure of {tool}: {e}");
+            }
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    // The findings may be saved, but a relint the client can't see is reported as failed
+    match db::record_relint(&state.pool, tool, state.clock.now(), status.as_u16().into()).await {
+        Ok(()) => status,
+        Err(e) => {
+            error!("Could not record the relint of {tool}: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Run the linter on a single validated biotoolsID, blocks until it finishes
+fn lint_tool(tool: &str, on_line: &(dyn Fn(&str) + Sync)) -> Result<StatusCode, LintFailure> {
+    run_linter(tool, &[], on_line).map(|(status, _)| status)
+}
+
+/// Run the linter with `--dry-run`, returns the findings it printed instead of saving them
+fn preview_tool(tool: &str) -> Result<(StatusCode, Vec<Message>), LintFailure> {
+    let (status, stdout) = run_linter(tool, &["--dry-run"], &|_| {})?;
+    Ok((status, parse_preview(&stdout)))
+}
+
+/// Findings printed by `linter/cli.py --dry-run`, one JSON database row per line
+pub fn parse_preview(stdout: &[u8]) -> Vec<Message> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|x| serde_json::from_str::<DatabaseEntry>(x).ok())
+        .map(Message::from)
+        .collect()
+}
+
+/// Run linter/cli.py on a single tool with extra arguments, returns the status and stdout
+///
+/// `on_line` gets each line of stdout and stderr as it is written, the linter logs its progress to stderr.
+fn run_linter(
+    tool: &str,
+    args: &[&str],
+    on_line: &(dyn Fn(&str) + Sync),
+) -> Result<(StatusCode, Vec<u8>), LintFailure> {
+    let script = "lint_from_server.sh";
+    let span = tracing::info_span!(
+        "linter",
+        tool,
+        args = ?args,
+        exit_code = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+
+    // Command takes arguments as literals so shell expansions is automatically escaped
+    let output = Command::new("bash")
+        .arg(script)
+        .arg(tool)
+        .arg("--no-color")
+        .arg("--exact")
+        .args(args)
+        .current_dir(LINTER_DIR)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            let stdout = child.stdout.take().unwrap();
+            let stderr = child.stderr.take().unwrap();
+            // Both pipes are read at once so neither fills up and blocks the linter
+            let (stdout, stderr) = std::thread::scope(|s| {
+                let stderr = s.spawn(|| read_lines(stderr, on_line));
+                (read_lines(stdout, on_line), stderr.join().unwrap())
+            });
+            Ok(Output {
+                status: child.wait()?,
+                stdout: stdout?,
+                stderr: stderr?,
+            })
+        });
+
+    debug!("Output from script: {:?}", output);
+
+    match output {
+        Ok(output) => {
+            info!("Output from script: {}", redacted_output(&output));
+            if let Some(code) = output.status.code() {
+                span.record("exit_code", code);
+            }
+            let status = linter_exit_status(output.status.code());
+            if status != StatusCode::INTERNAL_SERVER_ERROR {
+                return Ok((status, output.stdout));
+            }
+            error!("Linter failed: {}", redacted_output(&output));
+            Err(LintFailure {
+                exit_code: output.status.code(),
+                stderr: stderr_tail(&output.stderr),
+            })
+        }
+        Err(e) => {
+            error!("Could not run the linter: {}", redact_paths(&e.to_string()));
+            Err(LintFailure {
+                exit_code: None,
+                stderr: e.to_string(),
+            })
+        }
+    }
+}
+
+/// Read a pipe to the end, passing each line to `on_line`
+fn read_lines(pipe: impl Read, on_line: &(dyn Fn(&str) + Sync)) -> std::io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    for line in BufReader::new(pipe).split(b'\n') {
+        let line = line?;
+        on_line(&String::from_utf8_lossy(&line));
+        output.extend(line);
+        output.push(b'\n');
+    }
+    Ok(output)
+}
+
+/// Linter output for the logs, see `redact_paths`
+pub fn redacted_output(output: &std::process::Output) -> String {
+    redact_paths(&format!(
+        "{}, stdout: {:?}, stderr: {:?}",
+        output.status,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+/// Replace the linter directory with `<linter>` and the home directory with `~`
+///
+/// Logs may be shipped to third parties, the full output is only logged at debug level.
+pub fn redact_paths(text: &str) -> String {
+    REDACTED_PATHS
+        .iter()
+        .fold(text.to_owned(), |text, (path, replacement)| {
+            text.replace(path, replacement)
+        })
+}
+
+/// Last `RELINT_ERROR_LINES` lines of the linter output
+pub fn stderr_tail(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    lines[lines.len().saturating_sub(RELINT_ERROR_LINES)..].join("\n")
+}
+
+/// Whether to log the current sampled request, spreads the logged ones evenly instead of randomly
+///
+/// `rate` is the fraction (0 to 1) of successful read requests logged, `ServerConfig::log_sample_rate`.
+pub fn sample_request_log(rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    let n = SAMPLED_REQUESTS.fetch_add(1, Ordering::Relaxed) as f64;
+    (n * rate).floor() != ((n + 1.0) * rate).floor()
+}
+
+/// Link to the page of a tool under `base`, e.g. `ServerConfig::tool_url_base`
+pub fn tool_url(base: &str, tool: &str) -> String {
+    format!("{}/{tool}", base.trim_end_matches('/'))
+}
+
+/// Category of an error code, given by its prefix
+pub fn code_category(code: &str) -> &'static str {
+    match code.split('_').next() {
+        Some("URL") => "URL",
+        Some("EDAM") => "EDAM",
+        Some("DOI" | "PMID" | "PMCID") => "DOI-PMID",
+        _ => "other",
+    }
+}
+
+/// Canonical form of a biotoolsID, bio.tools matches IDs case-insensitively
+///
+/// Surrounding whitespace is removed and the ID is lowercased. Every endpoint taking an ID
+/// normalizes it before validating it, and stored IDs are compared in this form.
+pub fn normalize_tool_id(id: &str) -> String {
+    id.trim().to_lowercase()
+}
+
+/// Map the exit code of linter/cli.py to the status returned to the client
+pub fn linter_exit_status(code: Option<i32>) -> StatusCode {
+    match code {
+        Some(0) => StatusCode::OK,
+        Some(LINTER_EXIT_NOT_FOUND) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Get sender IP, prioritize X-Real-IP because of nginx
+fn client_ip(headers: &HeaderMap, socket_addr: &SocketAddr) -> String {
+    match headers.get("X-Real-IP") {
+        Some(ip) => ip.to_str().unwrap().to_string(),
+        None => socket_addr.ip().to_string(),
+    }
+}
+
+/// Severity-weighted score of a tool
+///
+/// The score is the sum of each finding's severity weight. By default critical findings weigh 10,
+/// high 5, medium 2, low 1 and linter errors 0, this can be changed with `--score-weights`.
+#[utoipa::path(
+    get,
+    path = "/api/tool/{id}/score",
+    operation_id = "getToolScore",
+    params(("id" = String, Path, description = "biotoolsID of the tool")),
+    responses(
+        (status = 200, description = "Score computed", body = ToolScore),
+        (status = 400, description = "Invalid biotoolsID"),
+    ),
+)]
+pub async fn tool_score_api(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    State(state): State<ServerState>,
+) -> Result<Json<ToolScore>, StatusCode> {
+    info_statement!(sampled state, headers, "API-TOOL-SCORE", "{}", id);
+
+    let id = normalize_tool_id(&id);
+    if !BIOTOOLS_ID_REGEX.is_match(&id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let counts = db::tool_severity_counts(&state.pool, &id).await;
+
+    Ok(Json(ToolScore {
+        score: state.config.score_weights.score(&counts),
+        tool: id,
+        counts,
+    }))
+}
+
+/// List every tool that has findings
+#[utoipa::path(
+    get,
+    path = "/api/tools",
+    operation_id = "listTools",
+    params(ToolsParams),
+    responses(
+        (status = 200, description = "Page of tools", body = ToolList),
+    ),
+)]
+pub async fn tools_api(
+    headers: HeaderMap,
+    Query(params): Query<ToolsParams>,
+    State(state): State<ServerState>,
+) -> Json<ToolList> {
+    let page = params.page.unwrap_or(0).max(0);
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    info_statement!(sampled state, headers, "API-TOOLS", "{} {}", page, limit);
+
+    let (results, count) = join!(
+        db::get_tools_paginated(&state.pool, page, limit),
+        db::count_total_unique_tools(&state.pool)
+    );
+    let count = count.unwrap();
+
+    Json(ToolList {
+        count,
+        next: if (page + 1) * limit < count {
+            Some(format!("?page={}&limit={limit}", page + 1))
+        } else {
+            None
+        },
+        previous: if page > 0 {
+            Some(format!("?page={}&limit={limit}", page - 1))
+        } else {
+            None
+        },
+        results,
+    })
+}
+
+/// All findings of a tool grouped by error code, with their counts and the newest finding time
+#[utoipa::path(
+    get,
+    path = "/api/tool/{id}",
+    operation_id = "getToolMessages",
+    params(("id" = String, Path, description = "biotoolsID of the tool"), ToolMessagesParams),
+    responses(
+        (status = 200, description = "Findings of the tool, or a SARIF log with `format=sarif`", body = ToolMessages),
+        (status = 400, description = "Invalid biotoolsID"),
+    ),
+)]
+pub async fn tool_messages_api(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(params): Query<ToolMessagesParams>,
+    State(state): State<ServerState>,
+) -> Result<Response, StatusCode> {
+    info_statement!(sampled state, headers, "API-TOOL", "{}", id);
+
+    let id = normalize_tool_id(&id);
+    if !BIOTOOLS_ID_REGEX.is_match(&id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut messages = db::get_messages_tool(&state.pool, &id).await;
+    for message in &mut messages {
+        message.link_tool(&state.config.tool_url_base);
+    }
+    if let Some(ToolMessagesFormat::Sarif) = params.format {
+        // SARIF viewers escape the text themselves
+        let log = sarif::report(&messages, &state.config.base_url);
+        return Ok((
+            [(header::CONTENT_TYPE, "application/sarif+json")],
+            Json(log),
+        )
+            .into_response());
+    }
+    for message in &mut messages {
+        message.escape();
+        message.autolink();
+    }
+
+    let last_linted = messages.iter().map(|x| x.time).max();
+    let mut codes: BTreeMap<String, CodeMessages> = BTreeMap::new();
+    for message in messages {
+        let group = codes.entry(message.code.clone()).or_default();
+        group.count += 1;
+        group.messages.push(message);
+    }
+    Ok(Json(ToolMessages {
+        tool: id,
+        last_linted,
+        codes,
+    })
+    .into_response())
+}
+
+/// Findings of several tools in a single request
+///
+/// Each tool gets up to `BATCH_TOOL_MESSAGES` findings sorted by code and time, tools without
+/// findings map to an empty list.
+#[utoipa::path(
+    post,
+    path = "/api/tools/messages",
+    operation_id = "getToolsMessages",
+    request_body(content = Vec<String>, description = "biotoolsIDs, at most 50"),
+    responses(
+        (status = 200, description = "A `{tool: [Message]}` map", body = BTreeMap<String, Vec<Message>>),
+        (status = 400, description = "Invalid biotoolsID or too many tools"),
+    ),
+)]
+pub async fn tools_messages_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+    Json(ids): Json<Vec<String>>,
+) -> Result<Json<BTreeMap<String, Vec<Message>>>, (StatusCode, String)> {
+    info_statement!(headers, "API-TOOLS-MESSAGES", "{} tools", ids.len());
+
+    if ids.len() > BATCH_TOOLS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("At most {BATCH_TOOLS} tools can be requested at once"),
+        ));
+    }
+    let ids: Vec<String> = ids.iter().map(|x| normalize_tool_id(x)).collect();
+    if let Some(id) = ids.iter().find(|x| !BIOTOOLS_ID_REGEX.is_match(x)) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid biotoolsID {id}")));
+    }
+
+    let mut groups: BTreeMap<String, Vec<Message>> =
+        ids.iter().map(|x| (x.clone(), Vec::new())).collect();
+    for mut message in db::get_messages_tools(&state.pool, &ids).await {
+        let messages = groups.get_mut(&normalize_tool_id(&message.tool)).unwrap();
+        if messages.len() < BATCH_TOOL_MESSAGES {
+            message.link_tool(&state.config.tool_url_base);
+            message.escape();
+            message.autolink();
+            messages.push(message);
+        }
+    }
+
+    Ok(Json(groups))
+}
+
+/// Findings, counts and last relint of a tool in a single request
+#[utoipa::path(
+    get,
+    path = "/api/tool/{id}/bundle",
+    operation_id = "getToolBundle",
+    params(("id" = String, Path, description = "biotoolsID of the tool")),
+    responses(
+        (status = 200, description = "Everything shown on the page of the tool", body = ToolBundle),
+        (status = 400, description = "Invalid biotoolsID"),
+    ),
+)]
+pub async fn tool_bundle_api(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    State(state): State<ServerState>,
+) -> Result<Json<ToolBundle>, StatusCode> {
+    info_statement!(sampled state, headers, "API-TOOL-BUNDLE", "{}", id);
+
+    let id = normalize_tool_id(&id);
+    if !BIOTOOLS_ID_REGEX.is_match(&id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (severity, codes, last_relint, mut messages) = join!(
+        db::tool_severity_counts(&state.pool, &id),
+        db::tool_codes(&state.pool, &id),
+        db::last_relint(&state.pool, &id),
+        db::get_messages_tool(&state.pool, &id),
+    );
+
+    let count = messages.len() as i64;
+    messages.truncate(TOOL_BUNDLE_MESSAGES);
+    for message in &mut messages {
+        message.link_tool(&state.config.tool_url_base);
+        message.escape();
+        message.autolink();
+    }
+
+    Ok(Json(ToolBundle {
+        tool: id,
+        severity,
+        codes,
+        last_relint,
+        count,
+        results: messages,
+    }))
+}
+
+/// When a tool was last relinted through the API and how it went
+#[utoipa::path(
+    get,
+    path = "/api/tool/{id}/last-relint",
+    operation_id = "getLastRelint",
+    params(("id" = String, Path, description = "biotoolsID of the tool")),
+    responses(
+        (status = 200, description = "Last relint of the tool", body = LastRelint),
+        (status = 400, description = "Invalid biotoolsID"),
+        (status = 404, description = "The tool was never relinted through the API"),
+    ),
+)]
+pub async fn last_relint_api(
+    headers: HeaderMap,
+    Path(id): Path<String>,
     State(state): State<ServerState>,
-) -> Html<String> {
-    info_statement!(headers, "WWW-INDEX", "");
-
-    // Simple statistics, multiple futures executing at once
-    let (error_count, oldest_entry_unix, tool_count, critical_count) = tokio::join!(
-        db::count_total_messages(&state.pool),
-        db::get_oldest_entry_unix(&state.pool),
-        db::count_total_unique_tools(&state.pool),
-        db::count_critical_messages(&state.pool),
-    );
-
-    // Timestamp
-    let d = UNIX_EPOCH + Duration::from_secs(oldest_entry_unix.try_into().unwrap());
-    let datetime = DateTime::<Utc>::from(d);
-    let timestamp = datetime.format("%Y-%m-%d %H:%M").to_string();
+) -> Result<Json<LastRelint>, StatusCode> {
+    info_statement!(sampled state, headers, "API-LAST-RELINT", "{}", id);
 
-    let mut c = Context::new();
-    c.insert("critical_count", &critical_count);
-    c.insert("error_count", &error_count);
-    c.insert("tool_count", &tool_count);
-    c.insert("last_time", &timestamp);
-    c.insert("search_value", "");
+    let id = normalize_tool_id(&id);
+    if !BIOTOOLS_ID_REGEX.is_match(&id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    Html(TEMPLATES.render("index.html", &c).unwrap())
+    db::last_relint(&state.pool, &id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
-/// Serve the stats page
-pub async fn serve_statistics_page(headers: HeaderMap) -> Html<String> {
-    info_statement!(headers, "WWW-STATISTICS", "");
+/// Details of the last failed relint of a tool, kept for `relint_error_ttl` seconds
+#[utoipa::path(
+    get,
+    path = "/api/tool/{id}/last-error",
+    operation_id = "getLastRelintError",
+    params(("id" = String, Path, description = "biotoolsID of the tool")),
+    responses(
+        (status = 200, description = "Last failed relint of the tool", body = RelintError),
+        (status = 400, description = "Invalid biotoolsID"),
+        (status = 401, description = "Missing or invalid `X-API-Key` header"),
+        (status = 404, description = "The tool has no recent failed relint"),
+    ),
+)]
+pub async fn last_relint_error_api(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    State(state): State<ServerState>,
+) -> Result<Json<RelintError>, StatusCode> {
+    info_statement!(sampled state, headers, "API-LAST-RELINT-ERROR", "{}", id);
 
-    let c = Context::new();
-    Html(TEMPLATES.render("statistics.html", &c).unwrap())
+    let id = normalize_tool_id(&id);
+    if !BIOTOOLS_ID_REGEX.is_match(&id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    db::last_relint_error(
+        &state.pool,
+        &id,
+        state.clock.now(),
+        state.config.relint_error_ttl,
+    )
+    .await
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
 }
 
-pub async fn serve_documentation_page(
+/// Register a URL notified of new findings of a tool or error code
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    operation_id = "createWebhook",
+    request_body = WebhookRequest,
+    responses(
+        (status = 201, description = "Subscription registered, keep the `secret` to verify deliveries", body = Webhook),
+        (status = 400, description = "Invalid URL, biotoolsID or code, or neither a tool nor a code given"),
+        (status = 401, description = "Missing or invalid `X-API-Key` header"),
+    ),
+)]
+pub async fn create_webhook_api(
     headers: HeaderMap,
-    Path(query_title): Path<String>,
-) -> Html<String> {
-    info_statement!(headers, "WWW-DOCUMENTATION", "{query_title}");
+    State(state): State<ServerState>,
+    Json(request): Json<WebhookRequest>,
+) -> Result<(StatusCode, Json<Webhook>), (StatusCode, String)> {
+    info_statement!(
+        headers,
+        "API-WEBHOOK-CREATE",
+        "{} {:?} {:?}",
+        request.url,
+        request.tool,
+        request.code
+    );
 
-    // https://stackoverflow.com/questions/56366947/how-does-a-rust-pathbuf-prevent-directory-traversal-attacks
-    let mut p = PathBuf::from_str(&query_title).unwrap();
-    if p.components()
-        .into_iter()
-        .any(|x| x == Component::ParentDir)
+    let bad_request = |x: &str| (StatusCode::BAD_REQUEST, x.to_owned());
+    match reqwest::Url::parse(&request.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => return Err(bad_request("Expected an HTTP or HTTPS url")),
+    }
+    let tool = request.tool.as_deref().map(normalize_tool_id);
+    if tool
+        .as_ref()
+        .is_some_and(|x| !BIOTOOLS_ID_REGEX.is_match(x))
     {
-        let c = Context::new();
-        return Html(TEMPLATES.render("error.html", &c).unwrap());
+        return Err(bad_request("Invalid biotoolsID"));
     }
-
-    if p.to_str() == Some("") {
-        p = PathBuf::from_str("index.md").unwrap();
+    let code = request.code.as_deref().map(|x| x.trim().to_uppercase());
+    if code.as_ref().is_some_and(String::is_empty) {
+        return Err(bad_request("Empty code"));
+    }
+    if tool.is_none() && code.is_none() {
+        return Err(bad_request("Expected a tool, a code or both"));
     }
 
-    let p = p.with_extension("md");
+    let secret = webhook::generate_secret();
+    let id = db::insert_webhook(
+        &state.pool,
+        &request.url,
+        tool.as_deref(),
+        code.as_deref(),
+        &secret,
+        state.clock.now(),
+    )
+    .await;
 
-    let markdown_path = PathBuf::from_str("documentation/").unwrap().join(p);
-    let markdown_string = fs::read_to_string(markdown_path).unwrap();
-    let parser = pulldown_cmark::Parser::new(&markdown_string);
-    let mut html_output = String::new();
-    pulldown_cmark::html::push_html(&mut html_output, parser);
+    Ok((
+        StatusCode::CREATED,
+        Json(Webhook {
+            id,
+            url: request.url,
+            tool,
+            code,
+            secret,
+        }),
+    ))
+}
 
-    let mut c = Context::new();
-    c.insert("content", &html_output);
-    Html(TEMPLATES.render("documentation.html", &c).unwrap())
+/// Remove a webhook subscription
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    operation_id = "deleteWebhook",
+    params(("id" = i32, Path, description = "ID of the subscription")),
+    responses(
+        (status = 204, description = "Subscription removed"),
+        (status = 401, description = "Missing or invalid `X-API-Key` header"),
+        (status = 404, description = "No such subscription"),
+    ),
+)]
+pub async fn delete_webhook_api(
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+    State(state): State<ServerState>,
+) -> StatusCode {
+    info_statement!(headers, "API-WEBHOOK-DELETE", "{}", id);
+
+    match db::delete_webhook(&state.pool, id).await {
+        true => StatusCode::NO_CONTENT,
+        false => StatusCode::NOT_FOUND,
+    }
 }
 
-pub async fn serve_documentation_index(headers: HeaderMap) -> Html<String> {
-    info_statement!(headers, "WWW-DOCUMENTATION", "");
+/// Error codes affecting a tool, without the individual messages
+#[utoipa::path(
+    get,
+    path = "/api/tool/{id}/codes",
+    operation_id = "getToolCodes",
+    params(("id" = String, Path, description = "biotoolsID of the tool")),
+    responses(
+        (status = 200, description = "Codes of the tool", body = [ToolCode]),
+        (status = 400, description = "Invalid biotoolsID"),
+    ),
+)]
+pub async fn tool_codes_api(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    State(state): State<ServerState>,
+) -> Result<Json<Vec<ToolCode>>, StatusCode> {
+    info_statement!(sampled state, headers, "API-TOOL-CODES", "{}", id);
 
-    let markdown_path = PathBuf::from_str("documentation/index.md").unwrap();
-    let markdown_string = fs::read_to_string(markdown_path).unwrap();
-    let parser = pulldown_cmark::Parser::new(&markdown_string);
-    let mut html_output = String::new();
-    pulldown_cmark::html::push_html(&mut html_output, parser);
+    let id = normalize_tool_id(&id);
+    if !BIOTOOLS_ID_REGEX.is_match(&id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    let mut c = Context::new();
-    c.insert("content", &html_output);
-    Html(TEMPLATES.render("documentation.html", &c).unwrap())
+    Ok(Json(db::tool_codes(&state.pool, &id).await))
 }
 
-/// Serve statistics JSON data
+/// Compare the findings of two tools
 #[utoipa::path(
     get,
-    path = "/api/statistics",
+    path = "/api/compare",
+    operation_id = "compareTools",
+    params(CompareParams),
     responses(
-         (status = 200, description = "Request successful", body = Statistics,
-         ),
+        (status = 200, description = "Comparison successful", body = ToolComparison),
+        (status = 400, description = "Invalid biotoolsID"),
     ),
-    params(
- ),
- )]
-pub async fn serve_statistics_api(
+)]
+pub async fn compare_api(
     headers: HeaderMap,
+    Query(params): Query<CompareParams>,
     State(state): State<ServerState>,
-) -> Json<Statistics> {
-    info_statement!(headers, "API-STATISTICS", "");
+) -> Result<Json<ToolComparison>, StatusCode> {
+    info_statement!(sampled state, headers, "API-COMPARE", "{} {}", params.a, params.b);
 
-    let json_str =
-        fs::read_to_string(state.stats_file_path).expect("Should have been able to read json file");
+    let (a, b) = (normalize_tool_id(&params.a), normalize_tool_id(&params.b));
+    if !BIOTOOLS_ID_REGEX.is_match(&a) || !BIOTOOLS_ID_REGEX.is_match(&b) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    let mut json: Statistics = serde_json::from_str(&json_str).expect("Could not parse JSON");
+    let (a, b) = join!(
+        db::tool_summary(&state.pool, &a),
+        db::tool_summary(&state.pool, &b)
+    );
 
-    // Make entries have all error types even if they will be null
-    for entry in &mut json.data {
-        for code in ERROR_CODES.clone() {
-            if !entry.error_types.contains_key(code) {
-                entry.error_types.insert(code.to_owned(), Value::Null);
-            }
-        }
-    }
+    let only_a = a
+        .codes
+        .keys()
+        .filter(|x| !b.codes.contains_key(*x))
+        .cloned()
+        .collect();
+    let only_b = b
+        .codes
+        .keys()
+        .filter(|x| !a.codes.contains_key(*x))
+        .cloned()
+        .collect();
 
-    return Json(json);
+    Ok(Json(ToolComparison {
+        a,
+        b,
+        only_a,
+        only_b,
+    }))
 }
 
-/// List every error or search for a specific one
+/// Compare a CSV of known `tool,code` findings with the findings of the linter
+///
+/// Tools are normalized like every biotoolsID and codes are matched case-insensitively.
+/// Only the tools listed in the CSV are compared, every result is sorted by tool and code.
 #[utoipa::path(
-   get,
-   path = "/api/search",
-   responses(
-        (status = 200, description = "Search successful", body = ApiResponse,
-        ),
-   ),
-   params(
-    APIQuery
-),
+    post,
+    path = "/api/compare/csv",
+    operation_id = "compareCsv",
+    request_body(content = String, description = "CSV with a `tool,code` header", content_type = "text/csv"),
+    responses(
+        (status = 200, description = "Comparison successful", body = CsvComparison),
+        (status = 400, description = "Malformed CSV, wrong header or invalid biotoolsID"),
+    ),
 )]
-pub async fn serve_search_api(
+pub async fn compare_csv_api(
     headers: HeaderMap,
     State(state): State<ServerState>,
-    Query(params): Query<APIQuery>,
-) -> Json<ApiResponse> {
-    // Get parameters
-    let query = params.query;
-    let page = params.page.unwrap_or(0);
-    let severity = params.severity;
-    let code = params.code;
+    body: String,
+) -> Result<Json<CsvComparison>, (StatusCode, String)> {
+    info_statement!(headers, "API-COMPARE-CSV", "{} lines", body.lines().count());
 
-    info_statement!(
-        headers,
-        "API-SEARCH",
-        "{:?}, {}, {:?}",
-        query,
-        page,
-        severity
-    );
+    let expected = parse_findings_csv(&body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
-    let code = match code {
-        None => "%%".to_owned(),
-        Some(x) => x,
-    };
+    let mut tools: Vec<String> = expected.iter().map(|x| x.tool.clone()).collect();
+    tools.dedup();
+    let linted: BTreeSet<ToolFinding> = db::tool_findings(&state.pool, &tools)
+        .await
+        .into_iter()
+        .collect();
 
-    let (messages, total_count) = match query.clone() {
-        None => {
-            join!(
-                db::get_messages_paginated(&state.pool, page, severity.clone(), code.clone()),
-                db::count_messages_paginated(&state.pool, severity.clone(), code)
-            )
-        }
-        Some(query) => {
-            join!(
-                db::get_messages_paginated_search(
-                    &state.pool,
-                    page,
-                    &query,
-                    severity.clone(),
-                    code.clone()
-                ),
-                db::count_messages_paginated_search(&state.pool, &query, severity.clone(), code)
-            )
+    Ok(Json(CsvComparison {
+        found: expected.intersection(&linted).cloned().collect(),
+        missed: expected.difference(&linted).cloned().collect(),
+        extra: linted.difference(&expected).cloned().collect(),
+    }))
+}
+
+/// Read the findings of a `tool,code` CSV, returns why it was rejected on error
+fn parse_findings_csv(body: &str) -> Result<BTreeSet<ToolFinding>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+
+    let header = reader.headers().map_err(|e| e.to_string())?;
+    if header.len() != 2
+        || !header[0].eq_ignore_ascii_case("tool")
+        || !header[1].eq_ignore_ascii_case("code")
+    {
+        return Err("Expected the header `tool,code`".to_owned());
+    }
+
+    let mut findings = BTreeSet::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let tool = normalize_tool_id(&record[0]);
+        if !BIOTOOLS_ID_REGEX.is_match(&tool) {
+            let line = record.position().map_or(0, |x| x.line());
+            return Err(format!("Invalid biotoolsID on line {line}"));
         }
-    };
+        findings.insert(ToolFinding {
+            tool,
+            code: record[1].to_uppercase(),
+        });
+    }
+    Ok(findings)
+}
 
-    Json(ApiResponse {
-        count: total_count,
-        next: if (page * 100) + 100 < total_count {
-            Some(format!("?page={}", page + 1))
-        } else {
-            None
-        },
-        previous: if page > 0 {
-            Some(format!("?page={}", page - 1))
-        } else {
-            None
-        },
-        results: messages,
-    })
+/// Tools with the largest increase in findings
+#[utoipa::path(
+    get,
+    path = "/api/trending",
+    operation_id = "getTrendingTools",
+    params(TrendingParams),
+    responses(
+        (status = 200, description = "Trending tools", body = [TrendingTool]),
+    ),
+)]
+pub async fn trending_api(
+    headers: HeaderMap,
+    Query(params): Query<TrendingParams>,
+    State(state): State<ServerState>,
+) -> Json<Vec<TrendingTool>> {
+    let window = params.window.unwrap_or(7).clamp(1, 365);
+    let limit = params.limit.unwrap_or(10).clamp(1, 100);
+    info_statement!(sampled state, headers, "API-TRENDING", "{} {}", window, limit);
+
+    Json(db::trending_tools(&state.pool, state.clock.now(), window, limit).await)
 }
 
-/// Relint a specific tool
-#[utoipa::path(post, path = "/api/lint", params(RelintParams))]
-pub async fn relint_api(
+/// Number of findings per code category
+pub type CategorySummary = BTreeMap<String, i64>;
+
+/// Every error code the linter reports, with what it means and where it is documented
+#[utoipa::path(
+    get,
+    path = "/api/codes",
+    operation_id = "listErrorCodes",
+    responses(
+        (status = 200, description = "Known error codes", body = [ErrorCode]),
+    ),
+)]
+pub async fn codes_api(
     headers: HeaderMap,
-    socket_addr: ConnectInfo<SocketAddr>,
-    Query(params): Query<RelintParams>,
     State(state): State<ServerState>,
-) -> StatusCode {
-    let input = params.tool.trim();
-    info_statement!(headers, "API-RELINT", "{}", input);
+) -> Json<&'static [ErrorCode]> {
+    info_statement!(sampled state, headers, "API-CODES", "");
 
-    let mut ips = state.ips.lock().unwrap();
-
-    // Get sender IP, prioritize X-Real-IP because of nginx
-    let ip: String = match headers.contains_key("X-Real-IP") {
-        true => headers
-            .get("X-Real-IP")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string(),
-        false => socket_addr.ip().to_string(),
-    };
+    Json(&ERROR_CODES)
+}
 
-    if ips.contains_key(&ip) {
-        info!("IP is already linting, aborting");
-        return StatusCode::TOO_MANY_REQUESTS;
-    }
-    if ips.values().any(|v| v == input) {
-        info!("Tool is already being linted, aborting");
-        return StatusCode::TOO_MANY_REQUESTS;
+/// Findings over the whole database per code category
+///
+/// Counted live from the messages table and cached for `CATEGORY_SUMMARY_TTL` since it is a full scan.
+#[utoipa::path(
+    get,
+    path = "/api/summary/categories",
+    operation_id = "getCategorySummary",
+    responses(
+        (status = 200, description = "Number of findings per category, `URL`, `EDAM`, `DOI-PMID` or `other`", body = BTreeMap<String, i64>),
+    ),
+)]
+pub async fn category_summary_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Json<CategorySummary> {
+    info_statement!(sampled state, headers, "API-SUMMARY-CATEGORIES", "");
+
+    if let Some((counted, summary)) = state.category_summary.lock().unwrap().as_ref() {
+        if counted.elapsed() < CATEGORY_SUMMARY_TTL {
+            return Json(summary.clone());
+        }
     }
 
-    // Escape injection attacks
-    // Regex taken from https://biotools.readthedocs.io/en/latest/api_usage_guide.html?highlight=biotoolsid#biotoolsid
-    let re = Regex::new(r"^[_\-.0-9a-zA-Z]*$").unwrap();
-    if !re.is_match(&input) {
-        info!("Input did not pass regex, aborting");
-        return StatusCode::INTERNAL_SERVER_ERROR;
+    let mut summary: CategorySummary = CODE_CATEGORIES.iter().map(|x| (x.to_string(), 0)).collect();
+    for (code, count) in db::code_counts(&state.pool).await {
+        *summary.get_mut(code_category(&code)).unwrap() += count;
     }
 
-    if input.contains("--lint-all") {
-        info!("Input contains -lint-all, aborting");
-        return StatusCode::INTERNAL_SERVER_ERROR;
+    *state.category_summary.lock().unwrap() = Some((Instant::now(), summary.clone()));
+    Json(summary)
+}
+
+/// A known error code with its number of findings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ErrorCodeStats {
+    pub code: String,
+    /// Category of the code, see `code_category`
+    pub category: String,
+    /// Number of findings with this code, 0 when there are none
+    pub count: i64,
+}
+
+/// Known error codes with their number of findings
+pub type ErrorCodeSummary = Vec<ErrorCodeStats>;
+
+/// Every known error code with its category and number of findings
+///
+/// Counted live from the messages table and cached for `ERROR_CODE_STATS_TTL` since it is a full scan.
+#[utoipa::path(
+    get,
+    path = "/api/errorcodes/stats",
+    operation_id = "getErrorCodeStats",
+    responses(
+        (status = 200, description = "Known error codes in their usual order with their counts", body = [ErrorCodeStats]),
+    ),
+)]
+pub async fn error_code_stats_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Json<ErrorCodeSummary> {
+    info_statement!(sampled state, headers, "API-ERRORCODES-STATS", "");
+
+    if let Some((counted, stats)) = state.error_code_stats.lock().unwrap().as_ref() {
+        if counted.elapsed() < ERROR_CODE_STATS_TTL {
+            return Json(stats.clone());
+        }
     }
 
-    // Insert IP and tool into server state
-    ips.insert(ip.clone(), input.to_string());
+    let counts: HashMap<String, i64> = db::code_counts(&state.pool).await.into_iter().collect();
+    let stats: ErrorCodeSummary = ERROR_CODES
+        .iter()
+        .map(|x| ErrorCodeStats {
+            code: x.code.to_owned(),
+            category: code_category(x.code).to_owned(),
+            count: counts.get(x.code).copied().unwrap_or(0),
+        })
+        .collect();
 
-    // Drop ips for concurrent requests
-    std::mem::drop(ips);
+    *state.error_code_stats.lock().unwrap() = Some((Instant::now(), stats.clone()));
+    Json(stats)
+}
 
-    let script = "lint_from_server.sh";
+/// Dataset counts as Prometheus gauges
+///
+/// Counted live from the messages table and cached for `DATA_METRICS_TTL`. The gauges are
+/// `biotools_linter_messages`, `biotools_linter_critical_messages`, `biotools_linter_tools`
+/// and `biotools_linter_messages_by_severity` with a `severity` label named like the fields of `SeverityCounts`.
+#[utoipa::path(
+    get,
+    path = "/api/metrics/data",
+    operation_id = "getDataMetrics",
+    responses(
+        (status = 200, description = "Prometheus text exposition format", content_type = "text/plain"),
+        (status = 500, description = "The counts could not be read from the database"),
+    ),
+)]
+pub async fn data_metrics_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Result<Response, StatusCode> {
+    info_statement!(sampled state, headers, "API-METRICS-DATA", "");
 
-    // Command takes arguments as literals so shell expansions is automatically escaped
-    let output = Command::new("bash")
-        .arg(script)
-        .arg(input)
-        .arg("--no-color")
-        .arg("--exact")
-        .current_dir("../")
-        .output();
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        cached_data_metrics(&state).await?,
+    )
+        .into_response())
+}
 
-    info!("Output from script: {:?}", output);
+/// Metrics of the server for Prometheus
+///
+/// HTTP requests per route, method and status as `biotools_linter_http_requests_total`, their latency as
+/// `biotools_linter_http_request_duration_seconds`, the duration of the search and tool queries as
+/// `biotools_linter_db_query_duration_seconds`, the queued and running relints as `biotools_linter_lint_jobs`
+/// and the dataset counts of `/api/metrics/data`.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    operation_id = "getMetrics",
+    responses(
+        (status = 200, description = "Prometheus text exposition format", content_type = "text/plain"),
+        (status = 500, description = "The counts could not be read from the database"),
+    ),
+)]
+pub async fn metrics_api(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Result<Response, StatusCode> {
+    info_statement!(sampled state, headers, "API-METRICS", "");
 
-    let mut ips = state.ips.lock().unwrap();
-    ips.remove(&ip);
+    let (queued, running) = db::count_pending_lint_jobs(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("Lint jobs could not be counted: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let name = "biotools_linter_lint_jobs";
+    let jobs = format!(
+        "# HELP {name} Relints queued with POST /api/lint by status\n# TYPE {name} gauge\n{name}{{status=\"queued\"}} {queued}\n{name}{{status=\"running\"}} {running}\n"
+    );
 
-    if let Ok(output) = output {
-        return match output.status.success() {
-            true => StatusCode::OK,
-            false => {
-                error!("{:#?}", output);
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render() + &jobs + &cached_data_metrics(&state).await?,
+    )
+        .into_response())
+}
 
-                return StatusCode::INTERNAL_SERVER_ERROR;
-            }
-        };
-    }
+/// The dataset counts in the Prometheus text format, recounted once `DATA_METRICS_TTL` passed
+async fn cached_data_metrics(state: &ServerState) -> Result<String, StatusCode> {
+    let cached = state
+        .data_metrics
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|(counted, _)| counted.elapsed() < DATA_METRICS_TTL)
+        .map(|(_, metrics)| metrics.clone());
+    let metrics = match cached {
+        Some(metrics) => metrics,
+        None => {
+            let (messages, critical, tools, severities) = tokio::join!(
+                db::count_total_messages(&state.pool),
+                db::count_critical_messages(&state.pool),
+                db::count_total_unique_tools(&state.pool),
+                db::severity_counts(&state.pool),
+            );
+            let metrics = match (messages, critical, tools, severities) {
+                (Ok(messages), Ok(critical), Ok(tools), Ok(severities)) => {
+                    data_metrics(messages, critical, tools, &severities)
+                }
+                _ => {
+                    error!("Dataset metrics could not be counted");
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+            *state.data_metrics.lock().unwrap() = Some((Instant::now(), metrics.clone()));
+            metrics
+        }
+    };
+    Ok(metrics)
+}
 
-    error!("{:#?}", output);
+/// Format the dataset counts in the Prometheus text exposition format
+fn data_metrics(messages: i64, critical: i64, tools: i64, severities: &SeverityCounts) -> String {
+    let gauge = |name: &str, help: &str, value: i64| {
+        format!("# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n")
+    };
+    let mut metrics = gauge(
+        "biotools_linter_messages",
+        "Findings in the database",
+        messages,
+    );
+    metrics += &gauge(
+        "biotools_linter_critical_messages",
+        "Critical findings in the database",
+        critical,
+    );
+    metrics += &gauge(
+        "biotools_linter_tools",
+        "Tools with at least one finding",
+        tools,
+    );
 
-    return StatusCode::INTERNAL_SERVER_ERROR;
+    let name = "biotools_linter_messages_by_severity";
+    metrics +=
+        &format!("# HELP {name} Findings in the database per severity\n# TYPE {name} gauge\n");
+    for (severity, count) in [
+        ("critical", severities.critical),
+        ("high", severities.high),
+        ("medium", severities.medium),
+        ("low", severities.low),
+        ("linter_error", severities.linter_error),
+        ("error", severities.error),
+        ("unknown", severities.unknown),
+    ] {
+        metrics += &format!("{name}{{severity=\"{severity}\"}} {count}\n");
+    }
+    metrics
 }
 
-/// Download data as csv
+/// Download data as CSV, JSON or NDJSON
 #[utoipa::path(get,
     path = "/api/download",
+    operation_id = "downloadCsv",
     params(DownloadParams),
     responses(
-        (status = 200, description = "Downloaded CSV"),
+        (status = 200, description = "Downloaded messages, streamed row by row. In CSV `severity` is the numeric level of `Severity`", content_type = ["text/csv", "application/json", "application/x-ndjson"]),
     ),
 )]
 pub async fn download_api(
@@ -574,37 +4073,121 @@ pub async fn download_api(
 ) -> impl IntoResponse {
     info_statement!(headers, "API-DOWNLOAD", "{:?}", params.query);
 
-    let messages = match params.query {
-        Some(query) => db::get_messages_all_search(&state.pool, &query).await,
-        None => db::get_messages_all(&state.pool).await,
+    let format = params.format.unwrap_or_default();
+    let delimiter = params.delimiter.unwrap_or_default().as_byte();
+    let with_tool_url = params.tool_url.unwrap_or(false);
+    let header = match format {
+        DownloadFormat::Csv => {
+            let mut columns = vec!["time", "timestamp", "tool"];
+            if with_tool_url {
+                columns.push("tool_url");
+            }
+            columns.extend(["code", "severity", "text"]);
+
+            let mut header = match params.bom {
+                Some(true) => "\u{feff}".as_bytes().to_vec(),
+                _ => Vec::new(),
+            };
+            let mut writer = csv_writer(delimiter);
+            writer.write_record(&columns).unwrap();
+            header.extend(writer.into_inner().unwrap());
+            header
+        }
+        DownloadFormat::Json => b"[".to_vec(),
+        DownloadFormat::Ndjson => Vec::new(),
     };
+    let raw = params.raw.unwrap_or(false);
 
-    let header = String::from("time,timestamp,tool,code,severity,text\n");
-    let data = messages
-        .into_iter()
-        .map(|x| {
-            format!(
-                "{},{},{},{},{},\"{}\"\n",
-                x.time,
-                x.timestamp,
-                x.tool,
-                x.code,
-                x.severity as i32,
-                x.text.replace('\n', "")
-            )
-        })
-        .reduce(|acc, e| acc + &e)
-        .unwrap();
+    let (mut sender, response_body) = axum::body::Body::channel();
+
+    tokio::spawn(async move {
+        if sender.send_data(header.into()).await.is_err() {
+            return;
+        }
+
+        // Rows are sent in chunks, each chunk is one write to the client
+        let mut chunks = db::stream_messages_all(
+            &state.pool,
+            params.query.as_deref(),
+            params.sort.unwrap_or_default(),
+            TimeRange::new(params.since, params.until),
+        )
+        .chunks(state.config.download_chunk_size);
+        let mut first = true;
+        while let Some(chunk) = chunks.next().await {
+            let mut writer = csv_writer(delimiter);
+            let mut rows = Vec::new();
+            for x in chunk {
+                let mut x = match x {
+                    Ok(x) => x,
+                    Err(e) => {
+                        // Aborting the body tells the client the download is incomplete
+                        error!("Download failed: {e}");
+                        sender.abort();
+                        return;
+                    }
+                };
+                x.link_tool(&state.config.tool_url_base);
+                if !raw {
+                    x.escape();
+                }
+                match format {
+                    DownloadFormat::Csv => {
+                        let mut record = vec![x.time.to_string(), x.timestamp, x.tool];
+                        if with_tool_url {
+                            record.push(x.tool_url);
+                        }
+                        record.extend([x.code, x.level.to_string(), x.text]);
+                        writer.write_record(&record).unwrap();
+                    }
+                    DownloadFormat::Json => {
+                        if !first {
+                            rows.push(b',');
+                        }
+                        serde_json::to_writer(&mut rows, &x).unwrap();
+                    }
+                    DownloadFormat::Ndjson => {
+                        serde_json::to_writer(&mut rows, &x).unwrap();
+                        rows.push(b'\n');
+                    }
+                }
+                first = false;
+            }
+            if let DownloadFormat::Csv = format {
+                rows = writer.into_inner().unwrap();
+            }
+
+            // Client disconnected, dropping the stream cancels the query
+            if sender.send_data(rows.into()).await.is_err() {
+                info!("Download client disconnected, aborting");
+                return;
+            }
+        }
+
+        if let DownloadFormat::Json = format {
+            let _ = sender.send_data("]".into()).await;
+        }
+    });
 
     (
         StatusCode::OK,
-        [(header::CONTENT_TYPE, "text/csv")],
-        header + &data,
+        [(header::CONTENT_TYPE, format.content_type())],
+        axum::body::boxed(response_body),
     )
 }
 
-pub async fn serve_sitemap(headers: HeaderMap) -> impl IntoResponse {
-    info_statement!(headers, "WWW-SITEMAP", "");
+/// CSV writer into memory, fields are quoted only if they contain the delimiter, a quote or a line break
+fn csv_writer(delimiter: u8) -> csv::Writer<Vec<u8>> {
+    csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new())
+}
+
+pub async fn serve_sitemap(
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> impl IntoResponse {
+    info_statement!(sampled state, headers, "WWW-SITEMAP", "");
 
     let manual_entries = vec![
         UrlEntry {
@@ -0,0 +1,14 @@
+/// Source of the current time, tests replace it to get reproducible time windows
+pub trait Clock: Send + Sync {
+    /// Current Unix timestamp in seconds
+    fn now(&self) -> i64;
+}
+
+/// Wall clock time, used by the server
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+}
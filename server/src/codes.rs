@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::api::Severity;
+
+/// An error code reported by the linter
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorCode {
+    /// The code as stored in findings
+    pub code: &'static str,
+    /// What the finding means
+    pub description: &'static str,
+    /// Severity the linter reports the code with, keep in sync with linter/rules
+    pub severity: Severity,
+    /// Documentation page explaining the code
+    pub documentation: &'static str,
+}
+
+/// Every error code the linter reports, shared by the API, statistics and templates
+pub static ERROR_CODES: [ErrorCode; 20] = [
+    ErrorCode {
+        code: "URL_INVALID",
+        description: "A URL could not be parsed",
+        severity: Severity::ReportHigh,
+        documentation: "/docs/URL_INVALID",
+    },
+    ErrorCode {
+        code: "URL_PERMANENT_REDIRECT",
+        description: "A URL permanently redirects to another one",
+        severity: Severity::ReportLow,
+        documentation: "/docs/URL_PERMANENT_REDIRECT",
+    },
+    ErrorCode {
+        code: "URL_BAD_STATUS",
+        description: "A website responded with an unsuccessful status code",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/URL_BAD_STATUS",
+    },
+    ErrorCode {
+        code: "URL_NO_SSL",
+        description: "A website does not support HTTPS",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/URL_NO_SSL",
+    },
+    ErrorCode {
+        code: "URL_UNUSED_SSL",
+        description: "A website supports HTTPS but the URL does not use it",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/URL_UNUSED_SSL",
+    },
+    ErrorCode {
+        code: "URL_TIMEOUT",
+        description: "A website took too long to respond",
+        severity: Severity::ReportHigh,
+        documentation: "/docs/URL_TIMEOUT",
+    },
+    ErrorCode {
+        code: "URL_SSL_ERROR",
+        description: "A website has an invalid or expired certificate",
+        severity: Severity::ReportHigh,
+        documentation: "/docs/URL_SSL_ERROR",
+    },
+    ErrorCode {
+        code: "URL_CONN_ERROR",
+        description: "The linter could not connect to a website",
+        severity: Severity::ReportHigh,
+        documentation: "/docs/URL_CONN_ERROR",
+    },
+    ErrorCode {
+        code: "URL_LINTER_ERROR",
+        description: "The linter failed to check a URL",
+        severity: Severity::LinterError,
+        documentation: "/docs/URL_LINTER_ERROR",
+    },
+    ErrorCode {
+        code: "EDAM_OBSOLETE",
+        description: "An EDAM term is obsolete",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/EDAM_OBSOLETE",
+    },
+    ErrorCode {
+        code: "EDAM_NOT_RECOMMENDED",
+        description: "An EDAM term is not recommended for annotation",
+        severity: Severity::ReportLow,
+        documentation: "/docs/EDAM_NOT_RECOMMENDED",
+    },
+    ErrorCode {
+        code: "EDAM_INVALID",
+        description: "An EDAM term is not in the ontology",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/EDAM_INVALID",
+    },
+    ErrorCode {
+        code: "DOI_BUT_NOT_PMID",
+        description: "A publication has a DOI but is missing its PMID",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/PublishingIDConversions",
+    },
+    ErrorCode {
+        code: "DOI_BUT_NOT_PMCID",
+        description: "A publication has a DOI but is missing its PMCID",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/PublishingIDConversions",
+    },
+    ErrorCode {
+        code: "PMID_BUT_NOT_DOI",
+        description: "A publication has a PMID but is missing its DOI",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/PublishingIDConversions",
+    },
+    ErrorCode {
+        code: "PMCID_BUT_NOT_DOI",
+        description: "A publication has a PMCID but is missing its DOI",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/PublishingIDConversions",
+    },
+    ErrorCode {
+        code: "URL_TOO_MANY_REDIRECTS",
+        description: "A URL redirects in a loop",
+        severity: Severity::ReportHigh,
+        documentation: "/docs/URL_TOO_MANY_REDIRECTS",
+    },
+    ErrorCode {
+        code: "EDAM_TOPIC_DISCREPANCY",
+        description: "A topic required by an annotated EDAM operation is missing",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/EDAM_TOPIC_DISCREPANCY",
+    },
+    ErrorCode {
+        code: "EDAM_INPUT_DISCREPANCY",
+        description: "An input required by an annotated EDAM operation is missing",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/EDAM_INPUT_DISCREPANCY",
+    },
+    ErrorCode {
+        code: "EDAM_OUTPUT_DISCREPANCY",
+        description: "An output required by an annotated EDAM operation is missing",
+        severity: Severity::ReportMedium,
+        documentation: "/docs/EDAM_OUTPUT_DISCREPANCY",
+    },
+];
+
+/// Documentation page of each code, for templates linking codes
+pub fn documentation_urls() -> BTreeMap<&'static str, &'static str> {
+    ERROR_CODES
+        .iter()
+        .map(|x| (x.code, x.documentation))
+        .collect()
+}
@@ -1,187 +1,913 @@
-use sqlx::{Pool, Postgres};
+use std::collections::BTreeMap;
 
-use crate::api::{Message, Severity};
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+use sqlx::{
+    postgres::{PgArguments, PgRow},
+    query::{Map, QueryScalar},
+    Pool, Postgres,
+};
+use tracing::instrument;
 
-/// What gets received from the database
+use crate::api::{
+    format_timestamp, CodeFilter, LastRelint, Message, RelintError, SearchCursor, SearchField,
+    SearchOrder, SearchPage, Severity, SeverityCounts, SeverityFilter, SortDirection, SortOrder,
+    TimeRange, ToolCode, ToolFinding, ToolSummary, TrendingTool,
+};
+use crate::jobs::LintJob;
+use crate::metrics;
+use crate::webhook::{Cursor, WebhookSubscription};
+
+/// What gets received from the database, or printed by `linter/cli.py --dry-run`
+#[derive(Deserialize)]
 pub struct DatabaseEntry {
     pub time: i64,
     pub tool: String,
     pub code: String,
+    // Selected but not part of the API message
+    #[allow(dead_code)]
     pub location: String,
     pub text: String,
     pub level: i32,
 }
 
-pub async fn count_total_messages(pool: &Pool<Postgres>) -> i64 {
+/// Create the tables owned by the server, the `messages` table is created by the linter
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn migrate(pool: &Pool<Postgres>) {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS tool_relints ( tool TEXT PRIMARY KEY, time BIGINT NOT NULL, status INTEGER NOT NULL );",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS tool_relint_errors ( tool TEXT PRIMARY KEY, time BIGINT NOT NULL, exit_code INTEGER, stderr TEXT NOT NULL );",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS lint_jobs ( id SERIAL PRIMARY KEY, tool TEXT NOT NULL, ip TEXT NOT NULL, status TEXT NOT NULL, result INTEGER, messages BIGINT, created BIGINT NOT NULL, started BIGINT, finished BIGINT );",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    // A tool and an IP have at most one queued or running job
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS lint_jobs_pending_tool ON lint_jobs (tool) WHERE status IN ('queued', 'running');",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS lint_jobs_pending_ip ON lint_jobs (ip) WHERE status IN ('queued', 'running');",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS webhooks ( id SERIAL PRIMARY KEY, url TEXT NOT NULL, tool TEXT, code TEXT, secret TEXT NOT NULL, last_time BIGINT NOT NULL, last_id INTEGER NOT NULL, created BIGINT NOT NULL );",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+/// Remember when a tool was last relinted and the status returned to the client
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn record_relint(
+    pool: &Pool<Postgres>,
+    tool: &str,
+    now: i64,
+    status: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO tool_relints (tool, time, status) VALUES ($1, $2, $3) ON CONFLICT (tool) DO UPDATE SET time = $2, status = $3",
+        tool,
+        now,
+        status
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn last_relint(pool: &Pool<Postgres>, tool: &str) -> Option<LastRelint> {
+    sqlx::query!(
+        "SELECT tool, time, status FROM tool_relints WHERE tool = $1",
+        tool
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+    .map(|x| LastRelint {
+        tool: x.tool,
+        time: x.time,
+        timestamp: format_timestamp(x.time),
+        status: x.status,
+    })
+}
+
+/// Store the details of a failed relint, replacing the previous failure of the tool and dropping expired ones
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn record_relint_error(
+    pool: &Pool<Postgres>,
+    tool: &str,
+    exit_code: Option<i32>,
+    stderr: &str,
+    now: i64,
+    ttl: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM tool_relint_errors WHERE time < $1", now - ttl)
+        .execute(pool)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO tool_relint_errors (tool, time, exit_code, stderr) VALUES ($1, $2, $3, $4) ON CONFLICT (tool) DO UPDATE SET time = $2, exit_code = $3, stderr = $4",
+        tool,
+        now,
+        exit_code,
+        stderr
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Last failed relint of a tool, unless it is older than `ttl` seconds at `now`
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn last_relint_error(
+    pool: &Pool<Postgres>,
+    tool: &str,
+    now: i64,
+    ttl: i64,
+) -> Option<RelintError> {
+    sqlx::query!(
+        "SELECT tool, time, exit_code, stderr FROM tool_relint_errors WHERE tool = $1 AND time >= $2",
+        tool,
+        now - ttl
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+    .map(|x| RelintError {
+        tool: x.tool,
+        time: x.time,
+        timestamp: format_timestamp(x.time),
+        exit_code: x.exit_code,
+        stderr: x.stderr,
+    })
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_total_messages(pool: &Pool<Postgres>) -> Result<i64, sqlx::Error> {
     sqlx::query_scalar!("SELECT COUNT(*) FROM messages")
-        .fetch_all(pool)
+        .fetch_one(pool)
         .await
-        .unwrap()[0]
-        .unwrap()
+        .map(|x| x.unwrap_or(0))
 }
 
-pub async fn count_total_unique_tools(pool: &Pool<Postgres>) -> i64 {
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_total_unique_tools(pool: &Pool<Postgres>) -> Result<i64, sqlx::Error> {
     sqlx::query_scalar!("SELECT COUNT(DISTINCT tool) FROM messages")
-        .fetch_all(pool)
+        .fetch_one(pool)
         .await
-        .unwrap()[0]
-        .unwrap()
+        .map(|x| x.unwrap_or(0))
+}
+
+/// Tools with at least one finding, sorted by ID
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_tools_paginated(pool: &Pool<Postgres>, page: i64, limit: i64) -> Vec<String> {
+    sqlx::query_scalar!(
+        "SELECT DISTINCT tool FROM messages ORDER BY tool LIMIT $1 OFFSET $2",
+        limit,
+        page * limit
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
 }
 
-pub async fn get_oldest_entry_unix(pool: &Pool<Postgres>) -> i64 {
+/// Time of the oldest message, `None` without messages
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_oldest_entry_unix(pool: &Pool<Postgres>) -> Result<Option<i64>, sqlx::Error> {
     sqlx::query_scalar!("SELECT MIN(time) from messages")
-        .fetch_all(pool)
+        .fetch_one(pool)
         .await
-        .unwrap()[0]
-        .unwrap()
 }
 
-pub async fn count_critical_messages(pool: &Pool<Postgres>) -> i64 {
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_critical_messages(pool: &Pool<Postgres>) -> Result<i64, sqlx::Error> {
     sqlx::query_scalar!("SELECT COUNT(*) FROM messages where level = 8")
-        .fetch_all(pool)
+        .fetch_one(pool)
         .await
-        .unwrap()[0]
-        .unwrap()
+        .map(|x| x.unwrap_or(0))
 }
 
-pub async fn get_messages_all(pool: &Pool<Postgres>) -> Vec<Message> {
-    let rows = sqlx::query_as!(
-        DatabaseEntry,
-        "SELECT time,tool,code,location,text,level FROM messages",
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn tool_severity_counts(pool: &Pool<Postgres>, tool: &str) -> SeverityCounts {
+    let rows = sqlx::query!(
+        "SELECT level, COUNT(*) as count FROM messages WHERE LOWER(tool) = $1 GROUP BY level",
+        tool
     )
     .fetch_all(pool)
     .await
     .unwrap();
 
-    // Process output from database entry to message
-    rows.into_iter().map(Message::from).collect()
+    count_by_severity(rows.into_iter().map(|x| (x.level, x.count.unwrap_or(0))))
 }
 
-pub async fn get_messages_all_search(pool: &Pool<Postgres>, query: &String) -> Vec<Message> {
-    let rows = sqlx::query_as!(
-        DatabaseEntry,
-        "SELECT time,tool,code,location,text,level FROM messages WHERE (tool ILIKE $1 OR code ILIKE $1)",
-        format!("%{}%", html_escape::encode_text(query)),
+/// Number of findings in the whole database per severity
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn severity_counts(pool: &Pool<Postgres>) -> Result<SeverityCounts, sqlx::Error> {
+    let rows = sqlx::query!("SELECT level, COUNT(*) as count FROM messages GROUP BY level")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(count_by_severity(
+        rows.into_iter().map(|x| (x.level, x.count.unwrap_or(0))),
+    ))
+}
+
+/// Sum `(level, count)` rows into their severities
+fn count_by_severity(rows: impl Iterator<Item = (i32, i64)>) -> SeverityCounts {
+    let mut counts = SeverityCounts::default();
+    for (level, count) in rows {
+        match Severity::from(level) {
+            Severity::ReportCritical => counts.critical += count,
+            Severity::ReportHigh => counts.high += count,
+            Severity::ReportMedium => counts.medium += count,
+            Severity::ReportLow => counts.low += count,
+            Severity::LinterError => counts.linter_error += count,
+            Severity::Error => counts.error += count,
+            Severity::Unknown => counts.unknown += count,
+        }
+    }
+    counts
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn tool_summary(pool: &Pool<Postgres>, tool: &str) -> ToolSummary {
+    let (severity, codes, last_relint) = tokio::join!(
+        tool_severity_counts(pool, tool),
+        sqlx::query!(
+            "SELECT code, COUNT(*) as count FROM messages WHERE LOWER(tool) = $1 GROUP BY code",
+            tool
+        )
+        .fetch_all(pool),
+        last_relint(pool, tool)
+    );
+
+    ToolSummary {
+        tool: tool.to_owned(),
+        severity,
+        codes: codes
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.code, x.count.unwrap_or(0)))
+            .collect(),
+        last_checked: last_relint.map(|x| x.time),
+    }
+}
+
+/// Error codes of a tool with their count and most severe finding, sorted by code
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn tool_codes(pool: &Pool<Postgres>, tool: &str) -> Vec<ToolCode> {
+    let rows = sqlx::query!(
+        "SELECT code, level, COUNT(*) as count FROM messages WHERE LOWER(tool) = $1 GROUP BY code, level",
+        tool
     )
     .fetch_all(pool)
     .await
     .unwrap();
 
-    // Process output from database entry to message
-    rows.into_iter().map(Message::from).collect()
+    let mut codes: BTreeMap<String, ToolCode> = BTreeMap::new();
+    for row in rows {
+        let severity = Severity::from(row.level);
+        let count = row.count.unwrap_or(0);
+        codes
+            .entry(row.code.clone())
+            .and_modify(|x| {
+                x.count += count;
+                if severity.rank() > x.max_severity.rank() {
+                    x.max_severity = severity;
+                }
+            })
+            .or_insert(ToolCode {
+                code: row.code,
+                count,
+                max_severity: severity,
+            });
+    }
+    codes.into_values().collect()
 }
 
-pub async fn get_messages_paginated(
+/// Number of findings of every error code
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn code_counts(pool: &Pool<Postgres>) -> Vec<(String, i64)> {
+    sqlx::query!("SELECT code, COUNT(*) as count FROM messages GROUP BY code")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| (x.code, x.count.unwrap_or(0)))
+        .collect()
+}
+
+/// The `limit` most common error codes with their number of findings
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn top_codes(
     pool: &Pool<Postgres>,
-    page: i64,
-    severity: Option<Severity>,
-    code: String,
-) -> Vec<Message> {
-    // This is a huge hack so I don't have to construct SQL queries manually
-    let (min_severity, max_severity): (i32, i32) = match severity {
-        Some(s) => {
-            let x = s.into();
-            (x, x)
-        }
-        None => (1, 7),
-    };
+    limit: i64,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    Ok(sqlx::query!("SELECT code, COUNT(*) as count FROM messages GROUP BY code ORDER BY count DESC, code LIMIT $1", limit)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|x| (x.code, x.count.unwrap_or(0)))
+        .collect())
+}
 
-    let rows = sqlx::query_as!(
-        DatabaseEntry,
-        "SELECT time,tool,code,location,text,level FROM messages WHERE level BETWEEN $1 AND $2 AND code ILIKE $3 LIMIT 100 OFFSET $4",
-        min_severity,
-        max_severity,
-        code,
-        (page as i64) * 100,
+/// Tools whose newest finding or relint is the oldest, the ones most likely to be out of date
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn stalest_tools(pool: &Pool<Postgres>, limit: i64) -> Vec<String> {
+    sqlx::query_scalar!(
+        r#"SELECT tool as "tool!" FROM (
+            SELECT tool, time FROM messages UNION ALL SELECT tool, time FROM tool_relints
+        ) checks
+        GROUP BY tool ORDER BY MAX(time), tool LIMIT $1"#,
+        limit
     )
     .fetch_all(pool)
     .await
-    .unwrap();
+    .unwrap()
+}
+
+/// Tools with the largest increase of findings in the last `window` days before `now` compared to the `window` days before
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn trending_tools(
+    pool: &Pool<Postgres>,
+    now: i64,
+    window: i64,
+    limit: i64,
+) -> Vec<TrendingTool> {
+    let window_start = now - window * 24 * 60 * 60;
+    let prior_start = window_start - window * 24 * 60 * 60;
+
+    let fetch = sqlx::query!(
+        r#"SELECT tool,
+            COUNT(*) FILTER (WHERE time >= $1) as "recent!",
+            COUNT(*) FILTER (WHERE time < $1) as "prior!"
+        FROM messages WHERE time >= $2 GROUP BY tool
+        ORDER BY COUNT(*) FILTER (WHERE time >= $1) - COUNT(*) FILTER (WHERE time < $1) DESC, tool
+        LIMIT $3"#,
+        window_start,
+        prior_start,
+        limit
+    )
+    .fetch_all(pool);
+    let rows = metrics::time_query("trending_tools", fetch).await.unwrap();
+
+    rows.into_iter()
+        .map(|x| TrendingTool {
+            tool: x.tool,
+            recent: x.recent,
+            prior: x.prior,
+            delta: x.recent - x.prior,
+        })
+        .collect()
+}
+
+/// `ILIKE` pattern matching `query` anywhere, `%`, `_` and `\\` in the query match themselves
+fn search_pattern(query: &str) -> String {
+    let escaped = html_escape::encode_text(query)
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// A query with its arguments bound, whose rows are mapped to `T`
+pub type BoundQuery<T> = Map<'static, Postgres, fn(PgRow) -> Result<T, sqlx::Error>, PgArguments>;
+
+/// Query of `get_messages_tool`, `LOWER(tool)` is indexed by the linter
+pub fn messages_tool_query(tool: &str) -> BoundQuery<DatabaseEntry> {
+    sqlx::query_as!(
+        DatabaseEntry,
+        "SELECT time,tool,code,location,text,level FROM messages WHERE LOWER(tool) = $1 ORDER BY code, time, id",
+        tool
+    )
+}
+
+/// Messages of a normalized biotoolsID
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_messages_tool(pool: &Pool<Postgres>, tool: &str) -> Vec<Message> {
+    let fetch = messages_tool_query(tool).fetch_all(pool);
+    let rows = metrics::time_query("tool_messages", fetch).await.unwrap();
 
     // Process output from database entry to message
     rows.into_iter().map(Message::from).collect()
 }
 
-pub async fn get_messages_paginated_search(
-    pool: &Pool<Postgres>,
-    page: i64,
-    query: &String,
-    severity: Option<Severity>,
-    code: String,
-) -> Vec<Message> {
-    let (min_severity, max_severity): (i32, i32) = match severity {
-        Some(s) => {
-            let x = s.into();
-            (x, x)
-        }
-        None => (1, 7),
-    };
-
-    let rows = sqlx::query_as!(
+/// Messages of every tool in `tools`, sorted by code and time like `get_messages_tool`
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_messages_tools(pool: &Pool<Postgres>, tools: &[String]) -> Vec<Message> {
+    let fetch = sqlx::query_as!(
         DatabaseEntry,
-        "SELECT time,tool,code,location,text,level FROM messages WHERE (tool ILIKE $1 OR code ILIKE $1) AND level BETWEEN $3 AND $4 AND code ILIKE $5 LIMIT 100 OFFSET $2",
-        format!("%{}%", html_escape::encode_text(query)),
-        (page as i64) * 100,
-        min_severity,
-        max_severity,
-        code
+        "SELECT time,tool,code,location,text,level FROM messages WHERE LOWER(tool) = ANY($1) ORDER BY code, time, id",
+        tools
+    )
+    .fetch_all(pool);
+    let rows = metrics::time_query("tools_messages", fetch).await.unwrap();
+
+    rows.into_iter().map(Message::from).collect()
+}
+
+/// Distinct `(tool, code)` pairs found in the given normalized biotoolsIDs
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn tool_findings(pool: &Pool<Postgres>, tools: &[String]) -> Vec<ToolFinding> {
+    sqlx::query_as!(
+        ToolFinding,
+        r#"SELECT DISTINCT LOWER(tool) as "tool!", code FROM messages WHERE LOWER(tool) = ANY($1)"#,
+        tools
     )
     .fetch_all(pool)
     .await
-    .unwrap();
+    .unwrap()
+}
+
+/// All messages, or those of tools and codes matching `query`, fetched row by row
+///
+/// Dropping the stream stops the query and releases its connection, a database error ends it.
+/// Severity is ordered by `Severity::rank`, ties are ordered newest first and then by insertion order.
+pub fn stream_messages_all<'a>(
+    pool: &'a Pool<Postgres>,
+    query: Option<&str>,
+    sort: SortOrder,
+    time: TimeRange,
+) -> impl Stream<Item = Result<Message, sqlx::Error>> + 'a {
+    sqlx::query_as!(
+        DatabaseEntry,
+        "SELECT time,tool,code,location,text,level FROM messages WHERE ($2 OR tool ILIKE $1 OR code ILIKE $1) AND time BETWEEN $4 AND $5 ORDER BY CASE WHEN $3 = 'severity_desc' THEN CASE level WHEN 8 THEN 4 WHEN 5 THEN 3 WHEN 6 THEN 2 WHEN 7 THEN 1 ELSE 0 END END DESC, CASE WHEN $3 = 'severity_asc' THEN CASE level WHEN 8 THEN 4 WHEN 5 THEN 3 WHEN 6 THEN 2 WHEN 7 THEN 1 ELSE 0 END END, CASE WHEN $3 = 'time_asc' THEN time END, time DESC, id",
+        search_pattern(query.unwrap_or_default()),
+        query.is_none(),
+        sort.as_str(),
+        time.since,
+        time.until,
+    )
+    .fetch(pool)
+    .map(|x| x.map(Message::from))
+}
+
+/// A row of `messages` with the `id` that orders ties and ends a page
+pub struct MessageRow {
+    pub id: i32,
+    pub time: i64,
+    pub tool: String,
+    pub code: String,
+    pub location: String,
+    pub text: String,
+    pub level: i32,
+}
+
+/// Messages of a page and the cursor of the page after it if this one is full
+fn into_page(rows: Vec<MessageRow>) -> (Vec<Message>, Option<SearchCursor>) {
+    let next = rows
+        .last()
+        .filter(|_| rows.len() == 100)
+        .map(|x| SearchCursor {
+            time: x.time,
+            id: x.id,
+        });
 
     // Process output from database entry to message
-    rows.into_iter().map(Message::from).collect()
+    let messages = rows
+        .into_iter()
+        .map(|x| {
+            Message::from(DatabaseEntry {
+                time: x.time,
+                tool: x.tool,
+                code: x.code,
+                location: x.location,
+                text: x.text,
+                level: x.level,
+            })
+        })
+        .collect();
+    (messages, next)
 }
 
+/// A page of messages and the cursor of the page after it if this one is full
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_messages_paginated(
+    pool: &Pool<Postgres>,
+    page: SearchPage,
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
+) -> (Vec<Message>, Option<SearchCursor>) {
+    let fetch = messages_page_query(page, severity, code, time).fetch_all(pool);
+    into_page(metrics::time_query("messages_page", fetch).await.unwrap())
+}
+
+/// Query of `get_messages_paginated`
+///
+/// Ties of the sort column are ordered newest first and then by `id`, findings of one relint
+/// share their `time` so this keeps pages stable. The default order is a plain `time DESC, id`, so
+/// the `messages_time_id` index created by the linter returns the page in order, from the cursor
+/// on. Cursors are only used with the default order, the others are picked with `CASE`.
+/// `code` holds `LIKE` patterns and `time` the window of times returned. The patterns are also
+/// bound as a range of codes, which with `time` is an index condition on the `messages_code_time`
+/// index created by the linter.
+pub fn messages_page_query(
+    page: SearchPage,
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
+) -> BoundQuery<MessageRow> {
+    let (offset, cursor) = page.start.offset_cursor();
+    let (code_from, code_to) = code.range();
+
+    match page.order == SearchOrder::default() {
+        // Without a cursor the page starts at the newest message
+        true => sqlx::query_as!(
+            MessageRow,
+            "SELECT id,time,tool,code,location,text,level FROM messages WHERE (cardinality($1::INTEGER[]) = 0 OR level = ANY($1)) AND NOT (level = ANY($9)) AND code LIKE ANY($2) AND NOT (code LIKE ANY($3)) AND code ~>=~ $7 AND code ~<~ $8 AND time BETWEEN $5 AND $6 AND time <= $10 AND (time < $10 OR id > $11) ORDER BY time DESC, id LIMIT 100 OFFSET $4",
+            &severity.include_levels(),
+            &code.include,
+            &code.exclude,
+            offset,
+            time.since,
+            time.until,
+            code_from,
+            code_to,
+            &severity.exclude_levels(),
+            cursor.map_or(i64::MAX, |x| x.time),
+            cursor.map_or(0, |x| x.id),
+        ),
+        false => sqlx::query_as!(
+            MessageRow,
+            "SELECT id,time,tool,code,location,text,level FROM messages WHERE (cardinality($1::INTEGER[]) = 0 OR level = ANY($1)) AND NOT (level = ANY($13)) AND code LIKE ANY($2) AND NOT (code LIKE ANY($3)) AND code ~>=~ $11 AND code ~<~ $12 AND time BETWEEN $5 AND $10 AND ($6::BIGINT IS NULL OR time < $6 OR (time = $6 AND id > $7)) ORDER BY CASE WHEN $8 = 'time' AND $9 THEN time END DESC, CASE WHEN $8 = 'time' AND NOT $9 THEN time END, CASE WHEN $8 = 'severity' AND $9 THEN CASE level WHEN 8 THEN 4 WHEN 5 THEN 3 WHEN 6 THEN 2 WHEN 7 THEN 1 ELSE 0 END END DESC, CASE WHEN $8 = 'severity' AND NOT $9 THEN CASE level WHEN 8 THEN 4 WHEN 5 THEN 3 WHEN 6 THEN 2 WHEN 7 THEN 1 ELSE 0 END END, CASE WHEN $8 = 'tool' AND $9 THEN tool END DESC, CASE WHEN $8 = 'tool' AND NOT $9 THEN tool END, CASE WHEN $8 = 'code' AND $9 THEN code END DESC, CASE WHEN $8 = 'code' AND NOT $9 THEN code END, time DESC, id LIMIT 100 OFFSET $4",
+            &severity.include_levels(),
+            &code.include,
+            &code.exclude,
+            offset,
+            time.since,
+            cursor.map(|x| x.time),
+            cursor.map_or(0, |x| x.id),
+            page.order.sort.as_str(),
+            page.order.direction == SortDirection::Desc,
+            time.until,
+            code_from,
+            code_to,
+            &severity.exclude_levels(),
+        ),
+    }
+}
+
+/// A page of messages matching `query`, ordered like `get_messages_paginated`
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn get_messages_paginated_search(
+    pool: &Pool<Postgres>,
+    page: SearchPage,
+    query: &str,
+    field: SearchField,
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
+) -> (Vec<Message>, Option<SearchCursor>) {
+    let fetch =
+        messages_search_page_query(page, query, field, severity, code, time).fetch_all(pool);
+    into_page(
+        metrics::time_query("messages_search_page", fetch)
+            .await
+            .unwrap(),
+    )
+}
+
+/// Query of `get_messages_paginated_search`, planned like `messages_page_query`
+pub fn messages_search_page_query(
+    page: SearchPage,
+    query: &str,
+    field: SearchField,
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
+) -> BoundQuery<MessageRow> {
+    let (search_tool, search_code, search_text, search_location) = field.columns();
+    let (offset, cursor) = page.start.offset_cursor();
+    let (code_from, code_to) = code.range();
+
+    match page.order == SearchOrder::default() {
+        true => sqlx::query_as!(
+            MessageRow,
+            "SELECT id,time,tool,code,location,text,level FROM messages WHERE (($6 AND tool ILIKE $1) OR ($7 AND code ILIKE $1) OR ($8 AND text ILIKE $1) OR ($10 AND location ILIKE $1)) AND (cardinality($3::INTEGER[]) = 0 OR level = ANY($3)) AND NOT (level = ANY($15)) AND code LIKE ANY($4) AND NOT (code LIKE ANY($5)) AND code ~>=~ $13 AND code ~<~ $14 AND time BETWEEN $9 AND $11 AND time <= $12 AND (time < $12 OR id > $16) ORDER BY time DESC, id LIMIT 100 OFFSET $2",
+            search_pattern(query),
+            offset,
+            &severity.include_levels(),
+            &code.include,
+            &code.exclude,
+            search_tool,
+            search_code,
+            search_text,
+            time.since,
+            search_location,
+            time.until,
+            cursor.map_or(i64::MAX, |x| x.time),
+            code_from,
+            code_to,
+            &severity.exclude_levels(),
+            cursor.map_or(0, |x| x.id),
+        ),
+        false => sqlx::query_as!(
+            MessageRow,
+            "SELECT id,time,tool,code,location,text,level FROM messages WHERE (($6 AND tool ILIKE $1) OR ($7 AND code ILIKE $1) OR ($8 AND text ILIKE $1) OR ($10 AND location ILIKE $1)) AND (cardinality($3::INTEGER[]) = 0 OR level = ANY($3)) AND NOT (level = ANY($18)) AND code LIKE ANY($4) AND NOT (code LIKE ANY($5)) AND code ~>=~ $16 AND code ~<~ $17 AND time BETWEEN $9 AND $15 AND ($11::BIGINT IS NULL OR time < $11 OR (time = $11 AND id > $12)) ORDER BY CASE WHEN $13 = 'time' AND $14 THEN time END DESC, CASE WHEN $13 = 'time' AND NOT $14 THEN time END, CASE WHEN $13 = 'severity' AND $14 THEN CASE level WHEN 8 THEN 4 WHEN 5 THEN 3 WHEN 6 THEN 2 WHEN 7 THEN 1 ELSE 0 END END DESC, CASE WHEN $13 = 'severity' AND NOT $14 THEN CASE level WHEN 8 THEN 4 WHEN 5 THEN 3 WHEN 6 THEN 2 WHEN 7 THEN 1 ELSE 0 END END, CASE WHEN $13 = 'tool' AND $14 THEN tool END DESC, CASE WHEN $13 = 'tool' AND NOT $14 THEN tool END, CASE WHEN $13 = 'code' AND $14 THEN code END DESC, CASE WHEN $13 = 'code' AND NOT $14 THEN code END, time DESC, id LIMIT 100 OFFSET $2",
+            search_pattern(query),
+            offset,
+            &severity.include_levels(),
+            &code.include,
+            &code.exclude,
+            search_tool,
+            search_code,
+            search_text,
+            time.since,
+            search_location,
+            cursor.map(|x| x.time),
+            cursor.map_or(0, |x| x.id),
+            page.order.sort.as_str(),
+            page.order.direction == SortDirection::Desc,
+            time.until,
+            code_from,
+            code_to,
+            &severity.exclude_levels(),
+        ),
+    }
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn count_messages_paginated(
     pool: &Pool<Postgres>,
-    severity: Option<Severity>,
-    code: String,
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
 ) -> i64 {
-    let (min_severity, max_severity): (i32, i32) = match severity {
-        Some(s) => {
-            let x = s.into();
-            (x, x)
-        }
-        None => (1, 7),
-    };
+    let fetch = count_messages_query(severity, code, time).fetch_all(pool);
+    metrics::time_query("messages_count", fetch).await.unwrap()[0].unwrap()
+}
 
+/// Query of `count_messages_paginated`, the code range and `time` are an index condition on
+/// `messages_code_time`
+pub fn count_messages_query(
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
+) -> QueryScalar<'static, Postgres, Option<i64>, PgArguments> {
+    let (code_from, code_to) = code.range();
     sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM messages WHERE level BETWEEN $1 AND $2 AND code ILIKE $3",
-        min_severity,
-        max_severity,
-        code
+        "SELECT COUNT(*) FROM messages WHERE (cardinality($1::INTEGER[]) = 0 OR level = ANY($1)) AND NOT (level = ANY($8)) AND code LIKE ANY($2) AND NOT (code LIKE ANY($3)) AND code ~>=~ $6 AND code ~<~ $7 AND time BETWEEN $4 AND $5",
+        &severity.include_levels(),
+        &code.include,
+        &code.exclude,
+        time.since,
+        time.until,
+        code_from,
+        code_to,
+        &severity.exclude_levels(),
     )
-    .fetch_all(pool)
-    .await
-    .unwrap()[0]
-        .unwrap()
 }
 
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
 pub async fn count_messages_paginated_search(
     pool: &Pool<Postgres>,
-    query: &String,
-    severity: Option<Severity>,
-    code: String,
+    query: &str,
+    field: SearchField,
+    severity: &SeverityFilter,
+    code: &CodeFilter,
+    time: TimeRange,
 ) -> i64 {
-    let (min_severity, max_severity): (i32, i32) = match severity {
-        Some(s) => {
-            let x = s.into();
-            (x, x)
-        }
-        None => (1, 7),
-    };
+    let (search_tool, search_code, search_text, search_location) = field.columns();
+    let (code_from, code_to) = code.range();
+
+    let fetch = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM messages WHERE (($5 AND tool ILIKE $1) OR ($6 AND code ILIKE $1) OR ($7 AND text ILIKE $1) OR ($9 AND location ILIKE $1)) AND (cardinality($2::INTEGER[]) = 0 OR level = ANY($2)) AND NOT (level = ANY($13)) AND code LIKE ANY($3) AND NOT (code LIKE ANY($4)) AND code ~>=~ $11 AND code ~<~ $12 AND time BETWEEN $8 AND $10",
+        search_pattern(query),
+        &severity.include_levels(),
+        &code.include,
+        &code.exclude,
+        search_tool,
+        search_code,
+        search_text,
+        time.since,
+        search_location,
+        time.until,
+        code_from,
+        code_to,
+        &severity.exclude_levels(),
+    )
+    .fetch_all(pool);
+    metrics::time_query("messages_search_count", fetch)
+        .await
+        .unwrap()[0]
+        .unwrap()
+}
 
+/// Subscribe `url` to findings found from `now` on, returns the subscription ID
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn insert_webhook(
+    pool: &Pool<Postgres>,
+    url: &str,
+    tool: Option<&str>,
+    code: Option<&str>,
+    secret: &str,
+    now: i64,
+) -> i32 {
     sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM messages WHERE (tool ILIKE $1 OR code ILIKE $1) AND level BETWEEN $2 AND $3 AND code ILIKE $4",
-        format!("%{}%", html_escape::encode_text(&query)),
-        min_severity,
-        max_severity,
-        code
+        "INSERT INTO webhooks (url, tool, code, secret, last_time, last_id, created) VALUES ($1, $2, $3, $4, $5, (SELECT COALESCE(MAX(id), 0) FROM messages), $5) RETURNING id",
+        url,
+        tool,
+        code,
+        secret,
+        now
     )
-    .fetch_all(pool)
+    .fetch_one(pool)
     .await
-    .unwrap()[0]
+    .unwrap()
+}
+
+/// Remove a subscription, returns false if it didn't exist
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn delete_webhook(pool: &Pool<Postgres>, id: i32) -> bool {
+    sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+        .execute(pool)
+        .await
         .unwrap()
+        .rows_affected()
+        > 0
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn webhooks(pool: &Pool<Postgres>) -> Result<Vec<WebhookSubscription>, sqlx::Error> {
+    sqlx::query_as!(
+        WebhookSubscription,
+        "SELECT id, url, tool, code, secret, last_time, last_id FROM webhooks ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Mark the findings up to the `(time, id)` cursor `last` as delivered
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn advance_webhook(pool: &Pool<Postgres>, id: i32, last: Cursor) {
+    sqlx::query!(
+        "UPDATE webhooks SET last_time = $2, last_id = $3 WHERE id = $1",
+        id,
+        last.0,
+        last.1
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+/// Up to `limit` findings matching a subscription after the `(time, id)` cursor `after` and found no
+/// later than `until`, with their cursors in that order
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn messages_after(
+    pool: &Pool<Postgres>,
+    after: Cursor,
+    until: i64,
+    tool: Option<&str>,
+    code: Option<&str>,
+    limit: i64,
+) -> Result<Vec<(Cursor, Message)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT id,time,tool,code,location,text,level FROM messages WHERE (time, id) > ($1, $2) AND time <= $3 AND ($4::TEXT IS NULL OR LOWER(tool) = $4) AND ($5::TEXT IS NULL OR code = $5) ORDER BY time, id LIMIT $6",
+        after.0,
+        after.1,
+        until,
+        tool,
+        code,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|x| {
+            let message = Message::from(DatabaseEntry {
+                time: x.time,
+                tool: x.tool,
+                code: x.code,
+                location: x.location,
+                text: x.text,
+                level: x.level,
+            });
+            ((x.time, x.id), message)
+        })
+        .collect())
+}
+
+/// Queue a relint, fails on the `lint_jobs_pending_tool` or `lint_jobs_pending_ip` index if the
+/// tool or the IP already has a job queued or running
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn insert_lint_job(
+    pool: &Pool<Postgres>,
+    tool: &str,
+    ip: &str,
+    now: i64,
+) -> Result<LintJob, sqlx::Error> {
+    sqlx::query_as!(
+        LintJob,
+        "INSERT INTO lint_jobs (tool, ip, status, created) VALUES ($1, $2, 'queued', $3) RETURNING id, tool, status, result, messages, created, started, finished",
+        tool,
+        ip,
+        now
+    )
+    .fetch_one(pool)
+    .await
+}
+
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn lint_job(pool: &Pool<Postgres>, id: i32) -> Option<LintJob> {
+    sqlx::query_as!(
+        LintJob,
+        "SELECT id, tool, status, result, messages, created, started, finished FROM lint_jobs WHERE id = $1",
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+}
+
+/// Number of findings of a tool
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_tool_messages(pool: &Pool<Postgres>, tool: &str) -> Result<i64, sqlx::Error> {
+    let count = sqlx::query_scalar!("SELECT COUNT(*) FROM messages WHERE LOWER(tool) = $1", tool)
+        .fetch_one(pool)
+        .await?;
+    Ok(count.unwrap_or(0))
+}
+
+/// Mark the oldest queued job as running and return it, concurrent workers never get the same job
+///
+/// Not traced, idle workers poll it every second.
+pub async fn claim_lint_job(
+    pool: &Pool<Postgres>,
+    now: i64,
+) -> Result<Option<LintJob>, sqlx::Error> {
+    sqlx::query_as!(
+        LintJob,
+        "UPDATE lint_jobs SET status = 'running', started = $1 WHERE id = (SELECT id FROM lint_jobs WHERE status = 'queued' ORDER BY id LIMIT 1 FOR UPDATE SKIP LOCKED) RETURNING id, tool, status, result, messages, created, started, finished",
+        now
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record the status a job finished with and the number of findings the tool has after it
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn finish_lint_job(
+    pool: &Pool<Postgres>,
+    id: i32,
+    result: i32,
+    messages: Option<i64>,
+    now: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE lint_jobs SET status = CASE WHEN $2 = 200 THEN 'finished' ELSE 'failed' END, result = $2, messages = $3, finished = $4 WHERE id = $1",
+        id,
+        result,
+        messages,
+        now
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Queue the jobs left running by a previous server process again
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn requeue_lint_jobs(pool: &Pool<Postgres>) {
+    sqlx::query!("UPDATE lint_jobs SET status = 'queued', started = NULL WHERE status = 'running'")
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+/// Run a trivial query to check the database is reachable
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn ping(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT 1").execute(pool).await.map(|_| ())
+}
+
+/// Number of queued and running lint jobs
+#[instrument(skip_all, fields(otel.kind = "client", db.system = "postgresql"))]
+pub async fn count_pending_lint_jobs(pool: &Pool<Postgres>) -> Result<(i64, i64), sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT COUNT(*) FILTER (WHERE status = 'queued') as "queued!", COUNT(*) FILTER (WHERE status = 'running') as "running!" FROM lint_jobs"#
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((row.queued, row.running))
 }
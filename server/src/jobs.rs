@@ -0,0 +1,206 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+use crate::{api, db, ServerState};
+
+/// How long an idle worker waits before looking for queued jobs again
+pub const JOB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lines buffered for a slow stream client before it skips ahead
+const LINT_OUTPUT_CAPACITY: usize = 256;
+
+/// A relint queued by `POST /api/lint`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LintJob {
+    pub id: i32,
+    /// biotoolsID of the relinted tool
+    pub tool: String,
+    /// `queued`, `running`, `finished` or `failed`
+    pub status: String,
+    /// HTTP status of the relint once it ended, `200` when finished, `404` if the tool is not on
+    /// bio.tools, `409` if the tool was being relinted by a bulk relint and `500` if the linter failed
+    pub result: Option<i32>,
+    /// Number of findings of the tool after a finished relint
+    pub messages: Option<i64>,
+    /// Unix timestamp of when the job was queued
+    pub created: i64,
+    /// Unix timestamp of when a worker started the job
+    pub started: Option<i64>,
+    /// Unix timestamp of when the job finished or failed
+    pub finished: Option<i64>,
+}
+
+/// Lines written by the linter of a running job, relayed by `/api/lint/stream/{job_id}`
+pub struct LintOutput {
+    /// Every line so far, sent first to clients that connect while the job runs
+    lines: Mutex<Vec<String>>,
+    /// Closed once the job ended and the output is dropped
+    sender: broadcast::Sender<String>,
+}
+
+impl LintOutput {
+    fn new() -> Self {
+        Self {
+            lines: Mutex::new(Vec::new()),
+            sender: broadcast::channel(LINT_OUTPUT_CAPACITY).0,
+        }
+    }
+
+    fn push(&self, line: String) {
+        // Holding the lock while sending keeps subscribers from missing or repeating a line
+        let mut lines = self.lines.lock().unwrap();
+        let _ = self.sender.send(line.clone());
+        lines.push(line);
+    }
+
+    /// The lines written so far and a receiver of the following ones
+    pub fn subscribe(&self) -> (Vec<String>, broadcast::Receiver<String>) {
+        let lines = self.lines.lock().unwrap();
+        (lines.clone(), self.sender.subscribe())
+    }
+}
+
+/// Why a relint could not be queued
+pub enum EnqueueError {
+    /// The tool already has a queued or running job
+    ToolPending,
+    /// The IP already has a queued or running job
+    IpPending,
+    Database(sqlx::Error),
+}
+
+/// Queue a relint of a validated biotoolsID requested by `ip`
+pub async fn enqueue(state: &ServerState, tool: &str, ip: &str) -> Result<LintJob, EnqueueError> {
+    db::insert_lint_job(&state.pool, tool, ip, state.clock.now())
+        .await
+        .map_err(
+            |e| match e.as_database_error().and_then(|x| x.constraint()) {
+                Some("lint_jobs_pending_tool") => EnqueueError::ToolPending,
+                Some("lint_jobs_pending_ip") => EnqueueError::IpPending,
+                _ => EnqueueError::Database(e),
+            },
+        )
+}
+
+/// Queue the jobs interrupted by a restart again and start the workers
+///
+/// Each worker runs one linter at a time, so at most `workers` queued relints run at once.
+pub async fn start(state: ServerState, workers: usize) {
+    db::requeue_lint_jobs(&state.pool).await;
+    for _ in 0..workers {
+        tokio::spawn(work(state.clone()));
+    }
+}
+
+async fn work(state: ServerState) {
+    loop {
+        if !run_next(&state).await {
+            tokio::time::sleep(JOB_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Run the oldest queued job, returns false if there was none
+pub async fn run_next(state: &ServerState) -> bool {
+    let job = match db::claim_lint_job(&state.pool, state.clock.now()).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return false,
+        Err(e) => {
+            error!("Could not claim a lint job: {e}");
+            return false;
+        }
+    };
+
+    // Hold a relint slot so bulk and scheduled relints skip the tool
+    let mut guard = JobGuard {
+        state,
+        id: job.id,
+        slot: None,
+    };
+    {
+        let mut ips = state.ips.lock().unwrap();
+        if !ips.values().any(|v| *v == job.tool) {
+            let slot = format!("job-{}", job.id);
+            ips.insert(slot.clone(), job.tool.clone());
+            guard.slot = Some(slot);
+        }
+    }
+
+    let status = match guard.slot {
+        None => StatusCode::CONFLICT,
+        Some(_) => {
+            let output = Arc::new(LintOutput::new());
+            state
+                .lint_output
+                .lock()
+                .unwrap()
+                .insert(job.id, output.clone());
+
+            // Linter logs may contain server paths like the server logs
+            api::run_relint(state, job.tool.clone(), move |line| {
+                output.push(api::redact_paths(line));
+            })
+            .await
+        }
+    };
+
+    let (status, messages) = match status {
+        StatusCode::OK => match db::count_tool_messages(&state.pool, &job.tool).await {
+            Ok(messages) => (status, Some(messages)),
+            Err(e) => {
+                error!("Could not count the findings of {}: {e}", job.tool);
+                (StatusCode::INTERNAL_SERVER_ERROR, None)
+            }
+        },
+        _ => (status, None),
+    };
+    info!(
+        "Lint job {} of {} finished with {}",
+        job.id, job.tool, status
+    );
+
+    // A job left running would block the tool and the IP until a restart, keep trying
+    while let Err(e) = db::finish_lint_job(
+        &state.pool,
+        job.id,
+        status.as_u16().into(),
+        messages,
+        state.clock.now(),
+    )
+    .await
+    {
+        error!("Could not record the end of lint job {}: {e}", job.id);
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+    }
+    true
+}
+
+/// Frees the relint slot and the output of a job however `run_next` ends
+///
+/// Dropped once the job is recorded, so streams ending with the output can report how it ended.
+struct JobGuard<'a> {
+    state: &'a ServerState,
+    id: i32,
+    /// `None` while the tool is being relinted by someone else
+    slot: Option<String>,
+}
+
+impl Drop for JobGuard<'_> {
+    fn drop(&mut self) {
+        // A poisoned lock already panicked elsewhere, panicking again while unwinding would abort
+        if let (Some(slot), Ok(mut ips)) = (&self.slot, self.state.ips.lock()) {
+            ips.remove(slot);
+        }
+        if let Ok(mut output) = self.state.lint_output.lock() {
+            output.remove(&self.id);
+        }
+    }
+}
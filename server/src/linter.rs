@@ -0,0 +1,51 @@
+//! Lint checks run by the server itself instead of the Python linter
+
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::api::Severity;
+
+pub mod edam;
+pub mod publications;
+pub mod urls;
+
+/// A problem found in a bio.tools entry, worded like the findings of the Python linter
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Finding {
+    /// Error code
+    pub code: &'static str,
+    /// Human readable error
+    pub text: String,
+    /// `/` separated path of the value in the entry, e.g. `link/0/url`
+    pub location: String,
+    pub severity: Severity,
+}
+
+/// Every string of a JSON value with its path, in document order
+pub fn flatten(value: &Value) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    flatten_into(value, "", &mut out);
+    out
+}
+
+fn flatten_into(value: &Value, path: &str, out: &mut Vec<(String, String)>) {
+    let join = |key: &dyn std::fmt::Display| match path {
+        "" => key.to_string(),
+        _ => format!("{path}/{key}"),
+    };
+    match value {
+        Value::String(x) => out.push((path.to_owned(), x.clone())),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten_into(item, &join(&index), out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                flatten_into(item, &join(key), out);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
@@ -0,0 +1,107 @@
+//! EDAM term checks, the same as `linter/rules/edam.py`, learn more at `https://edamontology.org/page`
+
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use super::Finding;
+use crate::api::Severity;
+
+/// Prefix of the class IDs of EDAM terms
+pub const EDAM_BASE: &str = "http://edamontology.org/";
+
+/// Columns of `EDAM.csv` read by the checks, the same indices the Python linter uses
+const CLASS_ID_COLUMN: usize = 0;
+const LABEL_COLUMN: usize = 1;
+const OBSOLETE_COLUMN: usize = 4;
+const OBSOLETE_COMMENT_COLUMN: usize = 11;
+const NOT_RECOMMENDED_COLUMN: usize = 75;
+
+/// A class of the ontology
+#[derive(Debug)]
+struct EdamClass {
+    label: String,
+    obsolete: bool,
+    /// Why the class is obsolete and what replaces it
+    obsolete_comment: String,
+    not_recommended: bool,
+}
+
+/// The classes of the EDAM ontology by class ID
+#[derive(Debug)]
+pub struct Edam {
+    classes: HashMap<String, EdamClass>,
+}
+
+impl Edam {
+    /// Load `EDAM.csv` from `https://edamontology.org/EDAM.csv`, the file the linter downloads
+    pub fn load(path: &Path) -> Result<Self, csv::Error> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let mut classes = HashMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let column = |x| record.get(x).unwrap_or_default();
+            let class_id = column(CLASS_ID_COLUMN);
+            if class_id.is_empty() {
+                continue;
+            }
+            let not_recommended = column(NOT_RECOMMENDED_COLUMN);
+            classes.insert(
+                class_id.to_owned(),
+                EdamClass {
+                    label: column(LABEL_COLUMN).to_owned(),
+                    obsolete: column(OBSOLETE_COLUMN) == "TRUE",
+                    obsolete_comment: column(OBSOLETE_COMMENT_COLUMN).to_owned(),
+                    not_recommended: !not_recommended.is_empty()
+                        && !not_recommended.eq_ignore_ascii_case("false"),
+                },
+            );
+        }
+        Ok(Self { classes })
+    }
+
+    /// Number of classes loaded
+    pub fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Check an EDAM term at `key`, either a class ID or its last part, e.g. `operation_0324`
+    pub fn check(&self, key: &str, term: &str) -> Option<Finding> {
+        let class_id = match term.contains("://") {
+            true => term.to_owned(),
+            false => format!("{EDAM_BASE}{term}"),
+        };
+        let (code, severity, text) = match self.classes.get(&class_id) {
+            None => (
+                "EDAM_INVALID",
+                Severity::ReportMedium,
+                format!("EDAM {term} at {key} is not a valid class ID."),
+            ),
+            Some(class) if class.obsolete => (
+                "EDAM_OBSOLETE",
+                Severity::ReportMedium,
+                format!(
+                    "EDAM \"{}\" at {key} is obsolete. ({})",
+                    class.label, class.obsolete_comment
+                ),
+            ),
+            Some(class) if class.not_recommended => (
+                "EDAM_NOT_RECOMMENDED",
+                Severity::ReportLow,
+                format!(
+                    "EDAM \"{}\" at {key} is not recommended for usage.",
+                    class.label
+                ),
+            ),
+            Some(_) => return None,
+        };
+        Some(Finding {
+            code,
+            text,
+            location: key.to_owned(),
+            severity,
+        })
+    }
+}
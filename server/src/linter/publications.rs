@@ -0,0 +1,193 @@
+//! Publication ID checks, the same as `linter/rules/publications.py`
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures_util::future::join_all;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::error;
+
+use super::Finding;
+use crate::api::Severity;
+
+/// NCBI PMC ID Converter, converts between the DOI, PMID and PMCID of a publication
+pub const IDCONV_URL: &str = "https://www.ncbi.nlm.nih.gov/pmc/utils/idconv/v1.0/";
+
+/// How long a conversion is reused, the IDs of a publication rarely change
+const IDCONV_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap();
+    /// Successful conversions by the identifier they were looked up with
+    static ref CACHE: Mutex<HashMap<String, (Instant, PublicationIds)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// The identifiers of a publication, as in the `publication` list of a bio.tools entry
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PublicationIds {
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub doi: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub pmid: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub pmcid: Option<String>,
+}
+
+/// Empty strings are missing IDs, the converter returns PMIDs as numbers or strings
+fn deserialize_id<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(x) if !x.trim().is_empty() => Some(x.trim().to_owned()),
+        Value::Number(x) => Some(x.to_string()),
+        _ => None,
+    })
+}
+
+#[derive(Deserialize)]
+struct IdconvResponse {
+    status: String,
+    #[serde(default)]
+    records: Vec<IdconvRecord>,
+}
+
+#[derive(Deserialize)]
+struct IdconvRecord {
+    #[serde(flatten)]
+    ids: PublicationIds,
+    /// `"false"` for records that are not live yet
+    live: Option<String>,
+}
+
+/// Check the publications of a bio.tools entry for IDs that are known to the converter but missing
+pub async fn check_entry(idconv_url: &str, entry: &Value) -> Vec<Finding> {
+    let Some(publications) = entry["publication"].as_array() else {
+        return Vec::new();
+    };
+    let checks = publications.iter().enumerate().map(|(index, publication)| {
+        let ids = PublicationIds::deserialize(publication).unwrap_or_default();
+        async move { check(idconv_url, &format!("publication/{index}"), &ids).await }
+    });
+    join_all(checks).await.into_iter().flatten().collect()
+}
+
+/// Check the IDs of a publication at `key`
+pub async fn check(idconv_url: &str, key: &str, ids: &PublicationIds) -> Vec<Finding> {
+    let Some(identifier) = ids
+        .doi
+        .as_ref()
+        .or(ids.pmid.as_ref())
+        .or(ids.pmcid.as_ref())
+    else {
+        return Vec::new();
+    };
+    let Some(converted) = convert(idconv_url, identifier).await else {
+        return Vec::new();
+    };
+
+    let mut findings = Vec::new();
+    let mut report = |code, text| {
+        findings.push(Finding {
+            code,
+            text,
+            location: key.to_owned(),
+            severity: Severity::ReportMedium,
+        })
+    };
+    if let Some(doi) = &ids.doi {
+        if ids.pmid.is_none() && converted.pmid.is_some() {
+            report(
+                "DOI_BUT_NOT_PMID",
+                format!(
+                    "Publication DOI {doi} (https://www.doi.org/{doi}) does not have a PMID in the database."
+                ),
+            );
+        }
+        if ids.pmcid.is_none() && converted.pmcid.is_some() {
+            report(
+                "DOI_BUT_NOT_PMCID",
+                format!(
+                    "Publication DOI {doi} (https://www.doi.org/{doi}) does not have a PMCID in the database."
+                ),
+            );
+        }
+    }
+    if ids.doi.is_none() && converted.doi.is_some() {
+        if let Some(pmid) = &ids.pmid {
+            report(
+                "PMID_BUT_NOT_DOI",
+                format!(
+                    "Publication PMID {pmid} (https://pubmed.ncbi.nlm.nih.gov/{pmid}) does not have a DOI in the database."
+                ),
+            );
+        }
+        if let Some(pmcid) = &ids.pmcid {
+            report(
+                "PMCID_BUT_NOT_DOI",
+                format!(
+                    "Publication PMCID {pmcid} (https://pubmed.ncbi.nlm.nih.gov/{pmcid}) does not have a DOI in the database."
+                ),
+            );
+        }
+    }
+    findings
+}
+
+/// Look up the other IDs of a publication, `None` if the converter doesn't know it or failed
+async fn convert(idconv_url: &str, identifier: &str) -> Option<PublicationIds> {
+    let cached = CACHE
+        .lock()
+        .unwrap()
+        .get(identifier)
+        .filter(|(converted, _)| converted.elapsed() < IDCONV_CACHE_TTL)
+        .map(|(_, ids)| ids.clone());
+    if cached.is_some() {
+        return cached;
+    }
+
+    let response = CLIENT
+        .get(idconv_url)
+        .query(&[
+            ("tool", "biotools-linter"),
+            ("email", "251814@mail.muni.cz"),
+            ("ids", identifier),
+            ("format", "json"),
+        ])
+        .send()
+        .await
+        .and_then(|x| x.error_for_status());
+    let response: IdconvResponse = match response {
+        Ok(x) => match x.json().await {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Invalid ID converter response for {identifier}: {e}");
+                return None;
+            }
+        },
+        Err(e) => {
+            error!("Error while making API request to idconv for {identifier}: {e}");
+            return None;
+        }
+    };
+    if response.status != "ok" {
+        return None;
+    }
+    let record = response.records.into_iter().next()?;
+    if record.live.as_deref() == Some("false") {
+        return None;
+    }
+
+    let mut cache = CACHE.lock().unwrap();
+    cache.retain(|_, (converted, _)| converted.elapsed() < IDCONV_CACHE_TTL);
+    cache.insert(identifier.to_owned(), (Instant::now(), record.ids.clone()));
+    Some(record.ids)
+}
@@ -0,0 +1,220 @@
+//! URL checks, the same as `linter/rules/url.py`
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures_util::future::join_all;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::redirect::Policy;
+use serde_json::Value;
+
+use super::{flatten, Finding};
+use crate::api::Severity;
+
+/// Slow websites hold up the whole lint, so this is far below the usual client defaults
+const URL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Redirects followed before a URL is reported as `URL_TOO_MANY_REDIRECTS`
+const MAX_REDIRECTS: usize = 30;
+
+/// How long the result of a URL is reused, many entries link the same websites
+const URL_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Browser-like so websites don't rate limit the linter, with a way to reach us
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 (Bio.tools linter, github.com/3top1a/biotools-linter)";
+
+lazy_static! {
+    static ref URL_REGEX: Regex = Regex::new(
+        r"^https?://(?:[a-zA-Z]|[0-9]|[$-_@.&+]|[!*\(\),]|(?:%[0-9a-fA-F][0-9a-fA-F]))+"
+    )
+    .unwrap();
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(URL_TIMEOUT)
+        .redirect(Policy::limited(MAX_REDIRECTS))
+        .build()
+        .unwrap();
+    /// Problems of recently checked URLs and when they were checked
+    static ref CACHE: Mutex<HashMap<String, (Instant, Vec<Problem>)>> = Mutex::new(HashMap::new());
+}
+
+/// A problem of a URL, independent of where in an entry it was found
+#[derive(Debug, Clone, PartialEq)]
+enum Problem {
+    Invalid,
+    Redirect,
+    BadStatus,
+    NoSsl,
+    UnusedSsl,
+    Timeout,
+    TooManyRedirects,
+    SslError(String),
+    ConnError,
+    LinterError(String),
+}
+
+impl Problem {
+    fn finding(&self, url: &str, key: &str) -> Finding {
+        let (code, severity, text) = match self {
+            Self::Invalid => (
+                "URL_INVALID",
+                Severity::ReportHigh,
+                format!(
+                    "URL {url} at {key} does not match a valid URL (there may be hidden unicode)."
+                ),
+            ),
+            Self::Redirect => (
+                "URL_PERMANENT_REDIRECT",
+                Severity::ReportLow,
+                format!("URL {url} at {key} returns a redirect."),
+            ),
+            Self::BadStatus => (
+                "URL_BAD_STATUS",
+                Severity::ReportMedium,
+                format!("URL {url} at {key} doesn't return ok status (>399)."),
+            ),
+            Self::NoSsl => (
+                "URL_NO_SSL",
+                Severity::ReportMedium,
+                format!("URL {url} at {key} does not use SSL."),
+            ),
+            Self::UnusedSsl => (
+                "URL_UNUSED_SSL",
+                Severity::ReportMedium,
+                format!("URL {url} at {key} does not start with https:// but site uses SSL."),
+            ),
+            Self::Timeout => (
+                "URL_TIMEOUT",
+                Severity::ReportHigh,
+                format!(
+                    "URL {url} at {key} timeouts after {} seconds.",
+                    URL_TIMEOUT.as_secs()
+                ),
+            ),
+            Self::TooManyRedirects => (
+                "URL_TOO_MANY_REDIRECTS",
+                Severity::ReportHigh,
+                format!("URL {url} at {key} failed exceeded {MAX_REDIRECTS} redirects."),
+            ),
+            Self::SslError(e) => (
+                "URL_SSL_ERROR",
+                Severity::ReportHigh,
+                format!("URL {url} at {key} returned an SSL error. ({e})"),
+            ),
+            Self::ConnError => (
+                "URL_CONN_ERROR",
+                Severity::ReportHigh,
+                format!("URL {url} at {key} returned a connection error, it may not exist."),
+            ),
+            Self::LinterError(e) => (
+                "URL_LINTER_ERROR",
+                Severity::LinterError,
+                format!("Error: {e} at {key} while checking {url}"),
+            ),
+        };
+        Finding {
+            code,
+            text,
+            location: key.to_owned(),
+            severity,
+        }
+    }
+}
+
+/// Check every URL of a bio.tools entry at once
+pub async fn check_entry(entry: &Value) -> Vec<Finding> {
+    let values = flatten(entry);
+    let checks = values.iter().map(|(key, value)| check(key, value));
+    join_all(checks).await.into_iter().flatten().collect()
+}
+
+/// Check the value at `key` of an entry if it is a URL or should be one
+pub async fn check(key: &str, value: &str) -> Vec<Finding> {
+    let is_url = URL_REGEX.is_match(value);
+    if !is_url && !key.ends_with("url") && !key.ends_with("uri") {
+        return Vec::new();
+    }
+    // FTP servers can't be checked
+    if value.starts_with("ftp://") {
+        return Vec::new();
+    }
+    // For example invisible unicode characters in a URL field
+    if !is_url {
+        return vec![Problem::Invalid.finding(value, key)];
+    }
+
+    let cached = CACHE
+        .lock()
+        .unwrap()
+        .get(value)
+        .filter(|(checked, _)| checked.elapsed() < URL_CACHE_TTL)
+        .map(|(_, problems)| problems.clone());
+    let problems = match cached {
+        Some(x) => x,
+        None => {
+            let problems = problems(value).await;
+            let mut cache = CACHE.lock().unwrap();
+            cache.retain(|_, (checked, _)| checked.elapsed() < URL_CACHE_TTL);
+            cache.insert(value.to_owned(), (Instant::now(), problems.clone()));
+            problems
+        }
+    };
+    problems.iter().map(|x| x.finding(value, key)).collect()
+}
+
+/// Request a URL, the body is never read so files are not downloaded
+async fn problems(url: &str) -> Vec<Problem> {
+    let response = match CLIENT.get(url).send().await {
+        Ok(x) => x,
+        Err(e) => return vec![request_problem(&e)],
+    };
+
+    let mut problems = Vec::new();
+    if reqwest::Url::parse(url).ok().as_ref() != Some(response.url()) {
+        problems.push(Problem::Redirect);
+    }
+    if response.status().is_client_error() || response.status().is_server_error() {
+        problems.push(Problem::BadStatus);
+    }
+    drop(response);
+
+    if let Some(rest) = url.strip_prefix("http://") {
+        // A website that can't be reached over HTTPS doesn't support it
+        match CLIENT.get(format!("https://{rest}")).send().await {
+            Ok(_) => problems.push(Problem::UnusedSsl),
+            Err(_) => problems.push(Problem::NoSsl),
+        }
+    }
+    problems
+}
+
+fn request_problem(e: &reqwest::Error) -> Problem {
+    if e.is_timeout() {
+        return Problem::Timeout;
+    }
+    if e.is_redirect() {
+        return Problem::TooManyRedirects;
+    }
+    if e.is_connect() || e.is_request() {
+        // The TLS errors are not exposed by reqwest, only their messages tell them apart
+        let mut source = e.source();
+        while let Some(x) = source {
+            let message = x.to_string();
+            let lower = message.to_lowercase();
+            if ["certificate", "ssl", "tls", "handshake"]
+                .iter()
+                .any(|x| lower.contains(x))
+            {
+                return Problem::SslError(message);
+            }
+            source = x.source();
+        }
+        return Problem::ConnError;
+    }
+    Problem::LinterError(e.to_string())
+}
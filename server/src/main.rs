@@ -1,18 +1,59 @@
+mod alert;
 mod api;
+mod clock;
+mod codes;
 mod db;
+mod jobs;
+mod linter;
+mod metrics;
+mod sarif;
+mod scheduler;
+mod telemetry;
 mod test;
+mod webhook;
 
 use api::{
-    ApiResponse, Message, __path_download_api, __path_serve_search_api,
-    __path_serve_statistics_api, relint_api, serve_documentation_index, serve_documentation_page,
-    serve_index_page, serve_search_api, serve_statistics_api, serve_statistics_page, Severity,
-    Statistics, StatisticsEntry, __path_relint_api, download_api, serve_sitemap,
+    __path_bulk_relint_api, __path_category_summary_api, __path_codes_api, __path_compare_api,
+    __path_compare_csv_api, __path_create_webhook_api, __path_data_metrics_api,
+    __path_delete_webhook_api, __path_download_api, __path_edam_validate_api,
+    __path_error_code_stats_api, __path_health_api, __path_last_relint_api,
+    __path_last_relint_error_api, __path_lint_publications_api, __path_lint_status_api,
+    __path_lint_stream_api, __path_lint_urls_api, __path_liveness_api, __path_metrics_api,
+    __path_regenerate_statistics_api, __path_relint_api, __path_serve_search_api,
+    __path_serve_search_head, __path_serve_search_partial, __path_serve_search_post_api,
+    __path_serve_statistics_api, __path_snapshots_api, __path_statistics_snapshot_api,
+    __path_tool_bundle_api, __path_tool_codes_api, __path_tool_messages_api, __path_tool_score_api,
+    __path_tools_api, __path_tools_messages_api, __path_trending_api, __path_version_api,
+    bulk_relint_api, category_summary_api, codes_api, compare_api, compare_csv_api,
+    create_webhook_api, data_metrics_api, delete_webhook_api, download_api, edam_validate_api,
+    error_code_stats_api, health_api, json_api_errors, last_relint_api, last_relint_error_api,
+    lint_publications_api, lint_status_api, lint_stream_api, lint_urls_api, liveness_api,
+    log_failed_requests, metrics_api, peer_ip_fallback, regenerate_statistics_api, relint_api,
+    request_id, require_api_key, serve_documentation_index, serve_documentation_page,
+    serve_index_page, serve_search_api, serve_search_head, serve_search_partial,
+    serve_search_post_api, serve_sitemap, serve_statistics_api, serve_statistics_page,
+    snapshots_api, statistics_snapshot_api, style_url, tool_bundle_api, tool_codes_api,
+    tool_messages_api, tool_score_api, tools_api, tools_messages_api, trending_api, version_api,
+    APIQuery, ApiError, ApiErrorResponse, ApiResponse, BulkRelintResult, CachedStatistics,
+    CategorySummary, CodeMessages, CsvComparison, CsvDelimiter, DownloadFormat, ErrorCodeStats,
+    ErrorCodeSummary, Health, HealthProbe, LastRelint, Message, RegenerateResponse, RelintError,
+    Resolution, ScoreWeights, SearchCount, SearchField, SearchSort, Severity, SeverityCounts,
+    Snapshot, SortDirection, SortOrder, Statistics, StatisticsEntry, ToolBundle, ToolCode,
+    ToolComparison, ToolFinding, ToolList, ToolMessages, ToolMessagesFormat, ToolScore,
+    ToolSummary, TrendingTool, VersionInfo, Webhook, WebhookRequest, PARSED_TEMPLATES,
 };
 use axum::{
-    routing::{get, post},
+    http::{header, HeaderValue},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
+use codes::ErrorCode;
+use jobs::{LintJob, LintOutput};
+use linter::{edam::Edam, publications, Finding};
 
+use axum_server::tls_rustls::RustlsConfig;
+use clock::{Clock, SystemClock};
 use dotenv::dotenv;
 
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
@@ -20,13 +61,17 @@ use std::{
     collections::HashMap,
     net::SocketAddr,
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime},
 };
 
 use env_logger::{Builder, Env};
 use std::io::Write;
-use tower_http::services::ServeFile;
-use utoipa::OpenApi;
+use tokio::sync::Semaphore;
+use tower_http::{
+    limit::RequestBodyLimitLayer, services::ServeFile, set_header::SetResponseHeaderLayer,
+};
+use utoipa::{openapi::Server, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
 #[macro_use]
@@ -44,6 +89,40 @@ FLAGS:
 OPTIONS:
   --port u16           Sets server port
   --stats path         Where to read statistics
+  --edam path          EDAM.csv checked by /api/edam/validate, downloaded by the linter
+                       on its first run [default: EDAM.csv]
+  --api-key string     Key required by the admin endpoints, also read from API_KEY
+  --critical-threshold i64
+                       Alert when the critical finding count exceeds this value
+  --alert-webhook url  Webhook (e.g. Slack) notified when the threshold is crossed
+  --alert-interval u64 Seconds between critical count checks [default: 300]
+  --log-sample-rate f64
+                       Fraction of successful read requests that are logged, failed
+                       and write requests are always logged [default: 1.0]
+  --auto-relint-interval u64
+                       Seconds between relints of the least recently checked tools,
+                       disabled when not given
+  --lint-workers usize Relints run at once, queued, bulk and scheduled alike [default: 2]
+  --webhook-interval u64
+                       Seconds between deliveries of new findings to webhook
+                       subscriptions [default: 60]
+  --body-limit usize   Maximum size of request bodies in bytes [default: 65536]
+  --max-search-offset i64
+                       Deepest row offset a search page may start at [default: 10000]
+  --max-query-length usize
+                       Longest search query in characters [default: 256]
+  --relint-error-ttl u64
+                       Seconds the details of a failed relint are kept [default: 604800]
+  --download-chunk-size usize
+                       Rows of a CSV download sent at once, larger chunks need fewer
+                       writes but buffer more rows in memory [default: 1000]
+  --tls-cert path      PEM certificate, serves HTTPS with HTTP/2 together with --tls-key
+  --tls-key path       PEM private key of the certificate
+  --tool-url-base url  Base of the tool links in messages [default: https://bio.tools/]
+  --base-url url       Public URL of this server, listed as the server of the API documentation
+                       [default: https://biotools-linter.biodata.ceitec.cz]
+  --score-weights list Severity weights of the tool score
+                       [default: critical=10,high=5,medium=2,low=1,linter_error=0]
 ";
 
 /// Server state passed to endpoints
@@ -55,18 +134,177 @@ pub struct ServerState {
     pub stats_file_path: PathBuf,
     /// Dictionary of IPs and tools that are being currently relinted
     pub ips: Arc<Mutex<HashMap<String, String>>>,
+    /// Output of the queued relints being run, by job ID
+    pub lint_output: Arc<Mutex<HashMap<i32, Arc<LintOutput>>>>,
+    /// One permit per linter allowed to run at once, set by `--lint-workers`
+    pub linters: Arc<Semaphore>,
+    /// Parsed and backfilled statistics, loaded on first request and reloaded when the file changes
+    pub statistics: Arc<RwLock<Option<CachedStatistics>>>,
+    /// Rendered documentation pages and the modification time of their markdown file
+    pub docs_cache: Arc<Mutex<HashMap<PathBuf, (SystemTime, String)>>>,
+    /// Findings per code category and when they were counted
+    pub category_summary: Arc<Mutex<Option<(Instant, CategorySummary)>>>,
+    /// Known error codes with their counts and when they were counted
+    pub error_code_stats: Arc<Mutex<Option<(Instant, ErrorCodeSummary)>>>,
+    /// Version reported by the linter, read on startup and refreshed with the statistics
+    pub linter_version: Arc<RwLock<Option<String>>>,
+    /// Prometheus text of the dataset counts and when they were counted
+    pub data_metrics: Arc<Mutex<Option<(Instant, String)>>>,
+    /// EDAM ontology read on startup, `None` if it could not be loaded
+    pub edam: Option<Arc<Edam>>,
+    /// Configuration parsed from arguments
+    pub config: Arc<ServerConfig>,
+    /// Current time of time windows, expiry and recorded relints
+    pub clock: Arc<dyn Clock>,
+}
+
+/// Server configuration set from the command line
+#[derive(Clone)]
+pub struct ServerConfig {
+    /// Key required in the `X-API-Key` header by admin endpoints, admin endpoints are disabled when `None`
+    pub api_key: Option<String>,
+    /// Weights used by the tool score endpoint
+    pub score_weights: ScoreWeights,
+    /// Maximum size of request bodies in bytes, larger requests are rejected with `413`
+    pub body_limit: usize,
+    /// Largest `page * 100` offset a search may request, deeper pages are rejected with `400`
+    pub max_search_offset: i64,
+    /// Longest search query in characters, longer queries are rejected with `400`
+    pub max_query_length: usize,
+    /// Seconds after which the details of a failed relint expire
+    pub relint_error_ttl: i64,
+    /// Rows of a CSV download sent to the client at once
+    pub download_chunk_size: usize,
+    /// Public URL the server is reached at, advertised as the OpenAPI server
+    pub base_url: String,
+    /// NCBI ID converter the publication checks look up missing IDs with
+    pub idconv_url: String,
+    /// Base of the tool page links in messages, the biotoolsID is appended to it
+    pub tool_url_base: String,
+    /// Fraction (0 to 1) of successful read requests that are logged, errors are always logged
+    pub log_sample_rate: f64,
 }
 
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            score_weights: ScoreWeights::default(),
+            body_limit: 64 * 1024,
+            max_search_offset: 10_000,
+            max_query_length: 256,
+            relint_error_ttl: 7 * 24 * 60 * 60,
+            download_chunk_size: 1000,
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            idconv_url: publications::IDCONV_URL.to_owned(),
+            tool_url_base: api::DEFAULT_TOOL_URL_BASE.to_owned(),
+            log_sample_rate: 1.0,
+        }
+    }
+}
+
+/// Public URL of the deployed server
+const DEFAULT_BASE_URL: &str = "https://biotools-linter.biodata.ceitec.cz";
+
 /// Auto generated API Documentation
 /// Remember to add additional paths and schemas
 #[derive(OpenApi)]
 #[openapi(
     info(description = "", title = "Biotools linter"),
-    paths(serve_search_api, serve_statistics_api, relint_api, download_api),
-    components(schemas(ApiResponse, Message, Statistics, StatisticsEntry, Severity,))
+    paths(
+        serve_search_api,
+        serve_search_post_api,
+        serve_search_head,
+        serve_search_partial,
+        serve_statistics_api,
+        snapshots_api,
+        statistics_snapshot_api,
+        relint_api,
+        lint_status_api,
+        lint_stream_api,
+        lint_urls_api,
+        lint_publications_api,
+        edam_validate_api,
+        bulk_relint_api,
+        download_api,
+        regenerate_statistics_api,
+        tools_api,
+        tool_messages_api,
+        tools_messages_api,
+        tool_score_api,
+        tool_bundle_api,
+        tool_codes_api,
+        last_relint_api,
+        last_relint_error_api,
+        create_webhook_api,
+        delete_webhook_api,
+        compare_api,
+        compare_csv_api,
+        trending_api,
+        category_summary_api,
+        error_code_stats_api,
+        codes_api,
+        data_metrics_api,
+        version_api,
+        health_api,
+        liveness_api,
+        metrics_api
+    ),
+    components(schemas(
+        ApiResponse,
+        SearchCount,
+        APIQuery,
+        Message,
+        Statistics,
+        StatisticsEntry,
+        Snapshot,
+        Severity,
+        SearchField,
+        SearchSort,
+        SortDirection,
+        CsvDelimiter,
+        DownloadFormat,
+        ToolMessagesFormat,
+        SortOrder,
+        RegenerateResponse,
+        VersionInfo,
+        Health,
+        HealthProbe,
+        Resolution,
+        ToolList,
+        ToolScore,
+        ToolCode,
+        ErrorCodeStats,
+        ErrorCode,
+        LastRelint,
+        RelintError,
+        LintJob,
+        Finding,
+        Webhook,
+        WebhookRequest,
+        ToolMessages,
+        CodeMessages,
+        ToolBundle,
+        SeverityCounts,
+        ToolSummary,
+        ToolComparison,
+        ToolFinding,
+        CsvComparison,
+        TrendingTool,
+        BulkRelintResult,
+        ApiError,
+        ApiErrorResponse,
+    ))
 )]
 struct ApiDoc;
 
+/// API documentation with the configured base URL as its server, so "Try it out" and generated clients target it
+fn openapi(config: &ServerConfig) -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    doc.servers = Some(vec![Server::new(&config.base_url)]);
+    doc
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize tracing
@@ -85,6 +323,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .init();
 
     dotenv().ok();
+    telemetry::init()?;
 
     // Parse arguments
     let mut pargs = pico_args::Arguments::from_env();
@@ -96,6 +335,80 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let stats_file_path: PathBuf = pargs
         .value_from_str("--stats")
         .expect("Need a statistics file");
+    let api_key: Option<String> = pargs
+        .opt_value_from_str("--api-key")
+        .unwrap()
+        .or(std::env::var("API_KEY").ok());
+    let critical_threshold: Option<i64> = pargs.opt_value_from_str("--critical-threshold").unwrap();
+    let alert_webhook: Option<String> = pargs.opt_value_from_str("--alert-webhook").unwrap();
+    let alert_interval: u64 = pargs.value_from_str("--alert-interval").unwrap_or(300);
+    let auto_relint_interval: Option<u64> =
+        pargs.opt_value_from_str("--auto-relint-interval").unwrap();
+    let webhook_interval: u64 = pargs.value_from_str("--webhook-interval").unwrap_or(60);
+    let lint_workers: usize = pargs.value_from_str("--lint-workers").unwrap_or(2);
+    let edam_path: PathBuf = pargs
+        .value_from_str("--edam")
+        .unwrap_or_else(|_| "EDAM.csv".into());
+    let score_weights: ScoreWeights = pargs
+        .opt_value_from_str("--score-weights")
+        .expect("Invalid score weights")
+        .unwrap_or_default();
+    let body_limit: usize = pargs.value_from_str("--body-limit").unwrap_or(64 * 1024);
+    let max_search_offset: i64 = pargs
+        .value_from_str("--max-search-offset")
+        .unwrap_or(10_000);
+    let tls_cert: Option<PathBuf> = pargs.opt_value_from_str("--tls-cert").unwrap();
+    let tls_key: Option<PathBuf> = pargs.opt_value_from_str("--tls-key").unwrap();
+    let tls = match (tls_cert, tls_key) {
+        (Some(cert), Some(key)) => Some(
+            RustlsConfig::from_pem_file(cert, key)
+                .await
+                .expect("Could not load the TLS certificate or key"),
+        ),
+        (None, None) => None,
+        _ => panic!("--tls-cert and --tls-key have to be given together"),
+    };
+    let max_query_length: usize = pargs.value_from_str("--max-query-length").unwrap_or(256);
+    let relint_error_ttl: i64 = pargs
+        .value_from_str("--relint-error-ttl")
+        .unwrap_or(7 * 24 * 60 * 60);
+    let download_chunk_size: usize = pargs
+        .value_from_str("--download-chunk-size")
+        .unwrap_or(1000);
+    let base_url: String = pargs
+        .value_from_str("--base-url")
+        .unwrap_or(DEFAULT_BASE_URL.to_owned());
+    let log_sample_rate: f64 = pargs.value_from_str("--log-sample-rate").unwrap_or(1.0);
+    let tool_url_base: String = pargs
+        .value_from_str("--tool-url-base")
+        .unwrap_or(api::DEFAULT_TOOL_URL_BASE.to_owned());
+
+    // A broken template fails startup instead of the first request rendering it
+    if let Err(e) = &*PARSED_TEMPLATES {
+        tracing::error!("Could not parse templates: {e}");
+        std::process::exit(1);
+    }
+
+    let linter_version = api::linter_version();
+    tracing::info!(
+        "Linter version {}",
+        linter_version.as_deref().unwrap_or("unknown")
+    );
+
+    // EDAM checks are optional, the ontology is only there once the linter downloaded it
+    let edam = match Edam::load(&edam_path) {
+        Ok(edam) => {
+            tracing::info!("Loaded {} EDAM classes", edam.class_count());
+            Some(Arc::new(edam))
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Could not load EDAM from {}, EDAM checks are disabled: {e}",
+                edam_path.display()
+            );
+            None
+        }
+    };
 
     // Connect to DB
     let conn_str = std::env::var("DATABASE_URL").expect(
@@ -105,43 +418,185 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .max_connections(5)
         .connect_lazy(&conn_str)
         .unwrap();
+    db::migrate(&pool).await;
 
     // Build server state
     let state = ServerState {
         pool,
         stats_file_path,
         ips: Arc::new(Mutex::new(HashMap::new())),
+        lint_output: Arc::new(Mutex::new(HashMap::new())),
+        linters: Arc::new(Semaphore::new(lint_workers.max(1))),
+        statistics: Arc::new(RwLock::new(None)),
+        docs_cache: Arc::new(Mutex::new(HashMap::new())),
+        category_summary: Arc::new(Mutex::new(None)),
+        error_code_stats: Arc::new(Mutex::new(None)),
+        data_metrics: Arc::new(Mutex::new(None)),
+        linter_version: Arc::new(RwLock::new(linter_version)),
+        edam,
+        config: Arc::new(ServerConfig {
+            api_key,
+            score_weights,
+            body_limit,
+            max_search_offset,
+            max_query_length,
+            relint_error_ttl,
+            download_chunk_size: download_chunk_size.max(1),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            idconv_url: publications::IDCONV_URL.to_owned(),
+            tool_url_base,
+            log_sample_rate: log_sample_rate.clamp(0.0, 1.0),
+        }),
+        clock: Arc::new(SystemClock),
     };
 
+    // Watch the critical count in the background
+    if let (Some(threshold), Some(webhook)) = (critical_threshold, alert_webhook) {
+        tokio::spawn(alert::watch_critical_count(
+            state.pool.clone(),
+            threshold,
+            webhook,
+            Duration::from_secs(alert_interval),
+        ));
+    }
+
+    // Keep the findings fresh by relinting the stalest tools in the background
+    if let Some(interval) = auto_relint_interval {
+        tokio::spawn(scheduler::relint_stalest(
+            state.clone(),
+            Duration::from_secs(interval.max(1)),
+        ));
+    }
+
+    // Run the relints queued through the API
+    jobs::start(state.clone(), lint_workers.max(1)).await;
+
+    // Send new findings to the webhook subscriptions
+    tokio::spawn(webhook::deliver_new_messages(
+        state.clone(),
+        Duration::from_secs(webhook_interval.max(1)),
+    ));
+
     let routes = app(&state);
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    tracing::info!("Listening on http://{}", addr);
-    axum::Server::bind(&addr)
-        .serve(routes.into_make_service_with_connect_info::<SocketAddr>())
-        .await
-        .unwrap();
+    let listener = std::net::TcpListener::bind(addr)?;
+    match tls {
+        Some(_) => tracing::info!("Listening on https://{}", addr),
+        None => tracing::info!("Listening on http://{}", addr),
+    }
+    serve(routes, listener, tls).await?;
 
     Ok(())
 }
 
+/// Serve HTTP/1.1 and HTTP/2, negotiated with ALPN over TLS or with prior knowledge (h2c) without it
+async fn serve(
+    routes: Router,
+    listener: std::net::TcpListener,
+    tls: Option<RustlsConfig>,
+) -> std::io::Result<()> {
+    let service = routes.into_make_service_with_connect_info::<SocketAddr>();
+    match tls {
+        Some(config) => {
+            axum_server::from_tcp_rustls(listener, config)
+                .serve(service)
+                .await
+        }
+        None => axum::Server::from_tcp(listener)
+            .map_err(std::io::Error::other)?
+            .serve(service)
+            .await
+            .map_err(std::io::Error::other),
+    }
+}
+
 /// Having a function that produces our app makes it easy to call it from tests
 /// without having to create an HTTP server.
 fn app(state: &ServerState) -> Router {
+    let admin = Router::new()
+        .route(
+            "/api/admin/regenerate-stats",
+            post(regenerate_statistics_api),
+        )
+        .route("/api/tool/:id/last-error", get(last_relint_error_api))
+        .route("/api/webhooks", post(create_webhook_api))
+        .route("/api/webhooks/:id", delete(delete_webhook_api))
+        .route("/api/lint/urls", post(lint_urls_api))
+        .route("/api/lint/publications", post(lint_publications_api))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ));
+
+    // The stylesheet URL contains its content hash, so browsers never have to revalidate it
+    let versioned_static = Router::new()
+        .nest_service(&style_url(), serve_static("static/style.css"))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=31536000, immutable"),
+        ));
+
     Router::new()
         .route("/", get(serve_index_page))
         .route("/docs/:query_title", get(serve_documentation_page))
         .route("/docs/", get(serve_documentation_index))
         .route("/docs", get(serve_documentation_index))
         .route("/statistics", get(serve_statistics_page))
-        .route("/api/search", get(serve_search_api))
+        .route(
+            "/api/search",
+            get(serve_search_api)
+                .post(serve_search_post_api)
+                .head(serve_search_head),
+        )
+        .route("/api/search/partial", get(serve_search_partial))
         .route("/api/statistics", get(serve_statistics_api))
+        .route("/api/statistics/:time", get(statistics_snapshot_api))
+        .route("/api/snapshots", get(snapshots_api))
         .route("/api/lint", post(relint_api))
+        .route("/api/lint/bulk", post(bulk_relint_api))
+        .route("/api/lint/status/:job_id", get(lint_status_api))
+        .route("/api/lint/stream/:job_id", get(lint_stream_api))
+        .route("/api/edam/validate", post(edam_validate_api))
         .route("/api/download", get(download_api))
-        .merge(SwaggerUi::new("/api/documentation").url("/api/openapi.json", ApiDoc::openapi()))
+        .route("/api/tools", get(tools_api))
+        .route("/api/tools/messages", post(tools_messages_api))
+        .route("/api/tool/:id", get(tool_messages_api))
+        .route("/api/tool/:id/score", get(tool_score_api))
+        .route("/api/tool/:id/bundle", get(tool_bundle_api))
+        .route("/api/tool/:id/codes", get(tool_codes_api))
+        .route("/api/tool/:id/last-relint", get(last_relint_api))
+        .route("/api/compare", get(compare_api))
+        .route("/api/compare/csv", post(compare_csv_api))
+        .route("/api/trending", get(trending_api))
+        .route("/api/summary/categories", get(category_summary_api))
+        .route("/api/errorcodes/stats", get(error_code_stats_api))
+        .route("/api/codes", get(codes_api))
+        .route("/api/metrics/data", get(data_metrics_api))
+        .route("/api/version", get(version_api))
+        .route("/healthz", get(health_api))
+        .route("/livez", get(liveness_api))
+        .route("/metrics", get(metrics_api))
+        .merge(admin)
+        .merge(versioned_static)
+        .merge(
+            SwaggerUi::new("/api/documentation").url("/api/openapi.json", openapi(&state.config)),
+        )
         .nest_service("/robots.txt", ServeFile::new("static/robots.txt"))
-        .nest_service("/style.css", ServeFile::new("static/style.css"))
-        .nest_service("/sitemap.xml", get(serve_sitemap))
+        .nest_service("/style.css", serve_static("static/style.css"))
+        .nest_service("/sitemap.xml", get(serve_sitemap).with_state(state.clone()))
+        .layer(RequestBodyLimitLayer::new(state.config.body_limit))
+        .layer(middleware::from_fn(json_api_errors))
+        .layer(middleware::from_fn(log_failed_requests))
+        .layer(middleware::from_fn(peer_ip_fallback))
+        .layer(middleware::from_fn(metrics::track_requests))
+        .layer(middleware::from_fn(telemetry::trace_requests))
+        .layer(middleware::from_fn(request_id))
         .with_state(state.clone())
 }
+
+/// Serve a static file, preferring a `.br` or `.gz` file next to it when the client accepts it
+fn serve_static(path: &str) -> ServeFile {
+    ServeFile::new(path).precompressed_br().precompressed_gzip()
+}
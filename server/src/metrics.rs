@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, fmt::Write, future::Future, sync::Mutex, time::Instant};
+
+use axum::{extract::MatchedPath, http::Request, middleware::Next, response::Response};
+
+/// Upper bounds in seconds of the latency histogram buckets
+const BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+lazy_static! {
+    /// Process wide so database functions can be timed without passing the server state around
+    static ref METRICS: Metrics = Metrics::default();
+}
+
+/// Request and query metrics collected since the server started
+#[derive(Default)]
+struct Metrics {
+    /// Responses by route, method and status
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    /// Response latencies by route and method
+    request_durations: Mutex<BTreeMap<(String, String), Histogram>>,
+    /// Durations by query name
+    query_durations: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+/// Cumulative Prometheus histogram
+#[derive(Default)]
+struct Histogram {
+    /// Observations at or below each of `BUCKETS`
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    /// Append the series of the histogram, `labels` are the other labels of its series
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (count, bound) in self.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+/// Escape a label value of the text exposition format
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Count requests and time responses by route
+///
+/// Routes are labeled with their pattern, e.g. `/api/tool/:id`, so the series don't grow with every
+/// tool. Requests that match no route share the `unmatched` label.
+pub async fn track_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_owned(), |x| x.as_str().to_owned());
+    let method = request.method().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let seconds = start.elapsed().as_secs_f64();
+
+    *METRICS
+        .requests
+        .lock()
+        .unwrap()
+        .entry((route.clone(), method.clone(), response.status().as_u16()))
+        .or_default() += 1;
+    METRICS
+        .request_durations
+        .lock()
+        .unwrap()
+        .entry((route, method))
+        .or_default()
+        .observe(seconds);
+    response
+}
+
+/// Time a database query, `query` names it in the metrics
+pub async fn time_query<F: Future>(query: &'static str, future: F) -> F::Output {
+    let start = Instant::now();
+    let output = future.await;
+    METRICS
+        .query_durations
+        .lock()
+        .unwrap()
+        .entry(query)
+        .or_default()
+        .observe(start.elapsed().as_secs_f64());
+    output
+}
+
+/// The request and query metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut out = String::new();
+
+    let name = "biotools_linter_http_requests_total";
+    let _ = writeln!(
+        out,
+        "# HELP {name} HTTP responses by route, method and status"
+    );
+    let _ = writeln!(out, "# TYPE {name} counter");
+    for ((route, method, status), count) in METRICS.requests.lock().unwrap().iter() {
+        let _ = writeln!(
+            out,
+            "{name}{{route=\"{}\",method=\"{method}\",status=\"{status}\"}} {count}",
+            label(route)
+        );
+    }
+
+    let name = "biotools_linter_http_request_duration_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {name} HTTP response latency by route and method"
+    );
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    for ((route, method), histogram) in METRICS.request_durations.lock().unwrap().iter() {
+        let labels = format!("route=\"{}\",method=\"{method}\"", label(route));
+        histogram.render(&mut out, name, &labels);
+    }
+
+    let name = "biotools_linter_db_query_duration_seconds";
+    let _ = writeln!(out, "# HELP {name} Database query duration by query");
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    for (query, histogram) in METRICS.query_durations.lock().unwrap().iter() {
+        histogram.render(&mut out, name, &format!("query=\"{query}\""));
+    }
+    out
+}
@@ -0,0 +1,163 @@
+use serde::Serialize;
+
+use crate::api::{Message, Severity};
+use crate::codes::ERROR_CODES;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// SARIF 2.1.0 log of the findings of a tool, readable by GitHub code scanning and other viewers
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifRun {
+    tool: SarifTool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifDriver {
+    name: &'static str,
+    information_uri: String,
+    /// One rule per error code found, in order of first appearance
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRule {
+    id: String,
+    short_description: SarifText,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+    default_configuration: SarifConfiguration,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifConfiguration {
+    level: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifText,
+    locations: Vec<SarifLocation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifText {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLocation {
+    physical_location: SarifPhysicalLocation,
+    logical_locations: Vec<SarifLogicalLocation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifArtifactLocation {
+    uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifLogicalLocation {
+    name: String,
+    kind: &'static str,
+}
+
+/// SARIF level of a severity
+///
+/// Linter errors and unknown levels are warnings, they don't say the entry itself is wrong.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::ReportCritical | Severity::ReportHigh => "error",
+        Severity::ReportMedium => "warning",
+        Severity::ReportLow => "note",
+        Severity::Error | Severity::LinterError | Severity::Unknown => "warning",
+    }
+}
+
+/// Convert the findings of a tool to a SARIF log, `base_url` is where the documentation is served
+pub fn report(messages: &[Message], base_url: &str) -> SarifLog {
+    let mut rules: Vec<SarifRule> = Vec::new();
+    let mut results = Vec::new();
+
+    for message in messages {
+        let rule_index = match rules.iter().position(|x| x.id == message.code) {
+            Some(x) => x,
+            None => {
+                let known = ERROR_CODES.iter().find(|x| x.code == message.code);
+                rules.push(SarifRule {
+                    id: message.code.clone(),
+                    short_description: SarifText {
+                        text: known.map_or(message.code.clone(), |x| x.description.to_owned()),
+                    },
+                    help_uri: known.map(|x| format!("{base_url}{}", x.documentation)),
+                    default_configuration: SarifConfiguration {
+                        level: level(known.map_or(message.severity, |x| x.severity)),
+                    },
+                });
+                rules.len() - 1
+            }
+        };
+
+        results.push(SarifResult {
+            rule_id: message.code.clone(),
+            rule_index,
+            level: level(message.severity),
+            message: SarifText {
+                text: message.text.clone(),
+            },
+            locations: vec![SarifLocation {
+                physical_location: SarifPhysicalLocation {
+                    artifact_location: SarifArtifactLocation {
+                        uri: message.tool_url.clone(),
+                    },
+                },
+                logical_locations: vec![SarifLogicalLocation {
+                    name: message.tool.clone(),
+                    kind: "module",
+                }],
+            }],
+        });
+    }
+
+    SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: "biotools-linter",
+                    information_uri: format!("{base_url}/"),
+                    rules,
+                },
+            },
+            results,
+        }],
+    }
+}
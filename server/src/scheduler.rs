@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::{api, db, ServerState};
+
+/// Tools relinted on each scheduled run
+const AUTO_RELINT_BATCH: i64 = 10;
+
+/// Relint slot held by the scheduler, so it never runs two lints at once
+/// and a tool being relinted by a client is skipped with a conflict
+const AUTO_RELINT_SLOT: &str = "auto-relint";
+
+/// Periodically relint the tools that were checked the longest time ago
+pub async fn relint_stalest(state: ServerState, interval: Duration) {
+    let mut timer = tokio::time::interval(interval);
+    // The first tick completes immediately, don't relint right on startup
+    timer.tick().await;
+
+    loop {
+        timer.tick().await;
+
+        {
+            let mut ips = state.ips.lock().unwrap();
+            if ips.contains_key(AUTO_RELINT_SLOT) {
+                continue;
+            }
+            ips.insert(AUTO_RELINT_SLOT.to_owned(), String::new());
+        }
+
+        let tools = db::stalest_tools(&state.pool, AUTO_RELINT_BATCH).await;
+        info!("Scheduled relint of {} tools: {:?}", tools.len(), tools);
+
+        for tool in tools {
+            let result = api::bulk_relint_tool(&state, AUTO_RELINT_SLOT, tool).await;
+            info!(
+                "Scheduled relint of {} finished with {}",
+                result.tool, result.status
+            );
+        }
+
+        state.ips.lock().unwrap().remove(AUTO_RELINT_SLOT);
+    }
+}
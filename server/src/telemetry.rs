@@ -0,0 +1,429 @@
+//! Tracing spans exported to an OpenTelemetry collector over OTLP/HTTP with JSON encoding
+//!
+//! Configured with the standard `OTEL_*` environment variables, nothing is exported unless an
+//! endpoint is set. Span fields become attributes, except for these:
+//!
+//! - `otel.name` replaces the span name
+//! - `otel.kind` is `server`, `client` or `internal` (default)
+//! - `otel.status_code` set to `ERROR` marks the span as failed
+//! - `traceparent` is a W3C trace context header, the span continues that remote trace
+//!
+//! The exporter is written against the stable OTLP/HTTP JSON protocol with `reqwest` and `serde_json`
+//! instead of `opentelemetry-otlp`, which brings its own SDK, protobuf and gRPC stack whose versions
+//! must move in lockstep with `tracing-opentelemetry`.
+
+use std::{
+    env,
+    fmt::{Debug, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::MatchedPath,
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::api::REQUEST_ID_HEADER;
+use tracing::{
+    error,
+    field::{Field, Visit},
+    info_span,
+    span::{Attributes, Id, Record},
+    subscriber::SetGlobalDefaultError,
+    Instrument, Subscriber,
+};
+use tracing_subscriber::{
+    filter::LevelFilter, layer::Context, prelude::*, registry::LookupSpan, Layer,
+};
+
+/// Finished spans waiting for export, spans are dropped when the collector can't keep up
+const SPAN_QUEUE_CAPACITY: usize = 4096;
+
+/// Most spans sent in one export request
+const EXPORT_BATCH: usize = 512;
+
+/// How often queued spans are exported
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where and how spans are exported
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Full URL spans are POSTed to, e.g. `http://localhost:4318/v1/traces`
+    pub traces_endpoint: String,
+    /// Sent with every export, e.g. for authentication
+    pub headers: Vec<(String, String)>,
+    /// `service.name` of the exported spans
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    /// Read `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` or `OTEL_EXPORTER_OTLP_ENDPOINT`,
+    /// `OTEL_EXPORTER_OTLP_HEADERS` and `OTEL_SERVICE_NAME`, `None` if no endpoint is set
+    pub fn from_env() -> Option<Self> {
+        let var = |name| env::var(name).ok().filter(|x| !x.trim().is_empty());
+        let traces_endpoint = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").or_else(|| {
+            var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .map(|x| format!("{}/v1/traces", x.trim_end_matches('/')))
+        })?;
+        let headers = var("OTEL_EXPORTER_OTLP_HEADERS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|x| x.split_once('='))
+            .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+            .collect();
+        Some(Self {
+            traces_endpoint,
+            headers,
+            service_name: var("OTEL_SERVICE_NAME").unwrap_or(env!("CARGO_PKG_NAME").to_owned()),
+        })
+    }
+}
+
+/// Export spans if the environment configures a collector, must be called inside the runtime
+///
+/// Log output is unaffected, `tracing` forwards every event to `log` with its `log-always` feature.
+/// The subscriber is installed without a `LogTracer`, the `log` logger belongs to `env_logger`.
+pub fn init() -> Result<(), SetGlobalDefaultError> {
+    let Some(config) = OtlpConfig::from_env() else {
+        return Ok(());
+    };
+    let (layer, spans) = OtlpLayer::new();
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::registry().with(layer.with_filter(LevelFilter::INFO)),
+    )?;
+    tracing::info!("Exporting traces to {}", config.traces_endpoint);
+    tokio::spawn(export(config, spans));
+    Ok(())
+}
+
+/// A span that ended, as exported
+#[derive(Debug, Clone)]
+pub struct FinishedSpan {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: String,
+    /// OTLP span kind, 1 internal, 2 server or 3 client
+    pub kind: u8,
+    pub error: bool,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(String, Value)>,
+}
+
+/// Collects spans as they close and queues them for export
+pub struct OtlpLayer {
+    sender: mpsc::Sender<FinishedSpan>,
+}
+
+impl OtlpLayer {
+    pub fn new() -> (Self, mpsc::Receiver<FinishedSpan>) {
+        let (sender, receiver) = mpsc::channel(SPAN_QUEUE_CAPACITY);
+        (Self { sender }, receiver)
+    }
+}
+
+/// Turns span fields into attributes and picks out the `otel.*` and `traceparent` fields
+#[derive(Default)]
+struct FieldVisitor {
+    attributes: Vec<(String, Value)>,
+    name: Option<String>,
+    kind: Option<u8>,
+    error: bool,
+    traceparent: Option<String>,
+}
+
+impl FieldVisitor {
+    fn record(&mut self, field: &Field, value: Value) {
+        let text = || {
+            value
+                .as_str()
+                .map(str::to_owned)
+                .unwrap_or(value.to_string())
+        };
+        match field.name() {
+            "otel.name" => self.name = Some(text()),
+            "otel.kind" => {
+                self.kind = Some(match text().as_str() {
+                    "server" => 2,
+                    "client" => 3,
+                    _ => 1,
+                })
+            }
+            "otel.status_code" => self.error = text() == "ERROR",
+            "traceparent" => self.traceparent = Some(text()),
+            name => {
+                self.attributes.retain(|(key, _)| key != name);
+                self.attributes.push((name.to_owned(), value));
+            }
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record(field, format!("{value:?}").into());
+    }
+}
+
+/// A span being recorded, kept in the extensions of the tracing span
+struct OpenSpan {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    start: SystemTime,
+    fields: FieldVisitor,
+}
+
+/// Trace and span ID of a `traceparent` header, `00-<trace id>-<span id>-<flags>`
+fn parse_traceparent(value: &str) -> Option<([u8; 16], [u8; 8])> {
+    let mut parts = value.trim().split('-');
+    let (version, trace_id, span_id) = (parts.next()?, parts.next()?, parts.next()?);
+    if version.len() != 2 || version == "ff" {
+        return None;
+    }
+    let mut trace = [0; 16];
+    let mut span = [0; 8];
+    decode_hex(trace_id, &mut trace)?;
+    decode_hex(span_id, &mut span)?;
+    // All zero IDs are invalid
+    match trace != [0; 16] && span != [0; 8] {
+        true => Some((trace, span)),
+        false => None,
+    }
+}
+
+fn decode_hex(hex: &str, out: &mut [u8]) -> Option<()> {
+    if hex.len() != out.len() * 2 || !hex.is_ascii() {
+        return None;
+    }
+    for (byte, pair) in out.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(())
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, x| {
+        let _ = write!(out, "{x:02x}");
+        out
+    })
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for OtlpLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+
+        // Continue the trace of the parent span, or of the caller if it sent its trace context
+        let parent = span.parent().and_then(|parent| {
+            let extensions = parent.extensions();
+            extensions
+                .get::<OpenSpan>()
+                .map(|x| (x.trace_id, x.span_id))
+        });
+        let (trace_id, parent_span_id) =
+            match parent.or_else(|| fields.traceparent.as_deref().and_then(parse_traceparent)) {
+                Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+                None => (rand::random(), None),
+            };
+
+        span.extensions_mut().insert(OpenSpan {
+            trace_id,
+            span_id: rand::random(),
+            parent_span_id,
+            start: SystemTime::now(),
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+                values.record(&mut open.fields);
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else {
+            return;
+        };
+        let finished = FinishedSpan {
+            trace_id: open.trace_id,
+            span_id: open.span_id,
+            parent_span_id: open.parent_span_id,
+            name: open.fields.name.unwrap_or(span.name().to_owned()),
+            kind: open.fields.kind.unwrap_or(1),
+            error: open.fields.error,
+            start: open.start,
+            end: SystemTime::now(),
+            attributes: open.fields.attributes,
+        };
+        // A full queue means the collector is down or slow, tracing must never block requests
+        let _ = self.sender.try_send(finished);
+    }
+}
+
+/// Send the queued spans to the collector in batches until the layer is dropped
+async fn export(config: OtlpConfig, mut spans: mpsc::Receiver<FinishedSpan>) {
+    let client = reqwest::Client::new();
+    let mut timer = tokio::time::interval(EXPORT_INTERVAL);
+    let mut batch = Vec::new();
+
+    loop {
+        timer.tick().await;
+        loop {
+            match spans.try_recv() {
+                Ok(span) => batch.push(span),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => return,
+            }
+            if batch.len() == EXPORT_BATCH {
+                send_batch(&client, &config, &batch).await;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            send_batch(&client, &config, &batch).await;
+            batch.clear();
+        }
+    }
+}
+
+/// POST spans to the collector, failed exports are logged and dropped
+pub async fn send_batch(client: &reqwest::Client, config: &OtlpConfig, spans: &[FinishedSpan]) {
+    let mut request = client
+        .post(&config.traces_endpoint)
+        .json(&export_request(&config.service_name, spans));
+    for (key, value) in &config.headers {
+        request = request.header(key, value);
+    }
+    match request.send().await.and_then(|x| x.error_for_status()) {
+        Ok(_) => {}
+        Err(e) => error!("Could not export {} spans: {e}", spans.len()),
+    }
+}
+
+/// OTLP attribute value, 64-bit integers are strings in the JSON encoding
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Bool(x) => json!({ "boolValue": x }),
+        Value::Number(x) if x.is_f64() => json!({ "doubleValue": x }),
+        Value::Number(x) => json!({ "intValue": x.to_string() }),
+        Value::String(x) => json!({ "stringValue": x }),
+        x => json!({ "stringValue": x.to_string() }),
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Body of an OTLP/HTTP JSON trace export
+pub fn export_request(service_name: &str, spans: &[FinishedSpan]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": encode_hex(&span.trace_id),
+                "spanId": encode_hex(&span.span_id),
+                "name": span.name,
+                "kind": span.kind,
+                "startTimeUnixNano": unix_nanos(span.start),
+                "endTimeUnixNano": unix_nanos(span.end),
+                "attributes": span.attributes.iter().map(|(key, value)| {
+                    json!({ "key": key, "value": any_value(value) })
+                }).collect::<Vec<_>>(),
+                "status": { "code": if span.error { 2 } else { 0 } },
+            });
+            if let Some(parent) = span.parent_span_id {
+                value["parentSpanId"] = encode_hex(&parent).into();
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{ "key": "service.name", "value": { "stringValue": service_name } }],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+/// Trace every request in a server span named by its route, continuing the caller's `traceparent`
+pub async fn trace_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_owned(), |x| x.as_str().to_owned());
+    let method = request.method().clone();
+    let traceparent = request
+        .headers()
+        .get(HeaderName::from_static("traceparent"))
+        .and_then(|x: &HeaderValue| x.to_str().ok())
+        .map(str::to_owned);
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|x| x.to_str().ok())
+        .map(str::to_owned);
+    let span = info_span!(
+        "http_request",
+        otel.name = %format!("{method} {route}"),
+        otel.kind = "server",
+        otel.status_code = tracing::field::Empty,
+        traceparent = traceparent.as_deref(),
+        http.request.method = %method,
+        http.route = %route,
+        request_id = request_id.as_deref(),
+        http.response.status_code = tracing::field::Empty,
+    );
+
+    let response = next.run(request).instrument(span.clone()).await;
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
@@ -5,16 +5,16 @@ mod tests {
     use tracing::Level;
     use tracing_subscriber::FmtSubscriber;
 
-    #[tokio::test]
-    async fn sql() {
-        use crate::*;
+    use crate::*;
 
+    /// Connect to the test database and build the app with the given configuration
+    async fn setup(config: ServerConfig) -> (TestClient, ServerState) {
         let subscriber = FmtSubscriber::builder()
             .compact()
             .with_max_level(Level::TRACE)
             .finish();
-        tracing::subscriber::set_global_default(subscriber)
-            .expect("setting default subscriber failed");
+        // Multiple tests share the same global subscriber
+        tracing::subscriber::set_global_default(subscriber).ok();
 
         dotenv().ok();
 
@@ -27,16 +27,149 @@ mod tests {
             .connect(&conn_str)
             .await
             .unwrap();
+        db::migrate(&pool).await;
+        // Concurrent tests would race to create a new index
+        LINTER_SCHEMA
+            .get_or_init(|| async {
+                for statement in linter_schema() {
+                    sqlx::query(statement).execute(&pool).await.unwrap();
+                }
+            })
+            .await;
 
         // Build server state
         let state = ServerState {
             pool,
             stats_file_path: "./sample_data.json".into(),
             ips: Arc::new(Mutex::new(HashMap::new())),
+            lint_output: Arc::new(Mutex::new(HashMap::new())),
+            linters: Arc::new(Semaphore::new(2)),
+            statistics: Arc::new(RwLock::new(None)),
+            docs_cache: Arc::new(Mutex::new(HashMap::new())),
+            category_summary: Arc::new(Mutex::new(None)),
+            error_code_stats: Arc::new(Mutex::new(None)),
+            data_metrics: Arc::new(Mutex::new(None)),
+            linter_version: Arc::new(RwLock::new(None)),
+            edam: None,
+            config: Arc::new(config),
+            clock: Arc::new(SystemClock),
         };
 
         let routes = app(&state);
-        let client = TestClient::new(routes);
+        (TestClient::new(routes), state)
+    }
+
+    static LINTER_SCHEMA: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+
+    /// `CREATE` statements of the `messages` table and its indexes, which are owned by the linter
+    fn linter_schema() -> Vec<&'static str> {
+        include_str!("../../linter/db.py")
+            .split('"')
+            .filter(|x| x.starts_with("CREATE "))
+            .collect()
+    }
+
+    /// Plan of a query as a prepared statement that has been reused, on a table of any size
+    async fn generic_plan<'q>(
+        pool: &Pool<Postgres>,
+        query: impl sqlx::Execute<'q, Postgres>,
+    ) -> String {
+        let mut conn = pool.acquire().await.unwrap();
+        for setting in [
+            "plan_cache_mode = force_generic_plan",
+            "enable_seqscan = off",
+            "enable_sort = off",
+        ] {
+            sqlx::query(&format!("SET {setting}"))
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+        }
+
+        // A generic plan doesn't depend on the arguments, the parameters show in it instead
+        let sql = query.sql();
+        sqlx::query(&format!("PREPARE explained AS {sql}"))
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let parameters = regex::Regex::new(r"\$(\d+)")
+            .unwrap()
+            .captures_iter(sql)
+            .map(|x| x[1].parse::<usize>().unwrap())
+            .max()
+            .unwrap_or(0);
+        let plan: Vec<String> = sqlx::query_scalar(&format!(
+            "EXPLAIN EXECUTE explained ({})",
+            vec!["NULL"; parameters].join(", ")
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+
+        sqlx::query("DEALLOCATE explained")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("RESET ALL").execute(&mut *conn).await.unwrap();
+        plan.join("\n")
+    }
+
+    /// A clock stopped at the given Unix timestamp
+    struct FixedClock(i64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> i64 {
+            self.0
+        }
+    }
+
+    /// Resource usage of the shared state, for asserting on concurrency limits
+    impl ServerState {
+        /// Clients currently holding a relint slot
+        fn relints_in_progress(&self) -> usize {
+            self.ips.lock().unwrap().len()
+        }
+
+        /// Open database connections, idle or in use
+        fn pool_connections(&self) -> u32 {
+            self.pool.size()
+        }
+    }
+
+    /// Replace all messages of a tool with the given `(code, level)` findings
+    async fn insert_messages(pool: &Pool<Postgres>, tool: &str, findings: &[(&str, i32)]) {
+        delete_messages(pool, tool).await;
+        for (code, level) in findings {
+            insert_message(pool, tool, code, *level, 1_700_000_000).await;
+        }
+    }
+
+    async fn delete_messages(pool: &Pool<Postgres>, tool: &str) {
+        sqlx::query("DELETE FROM messages WHERE tool = $1")
+            .bind(tool)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn insert_message(pool: &Pool<Postgres>, tool: &str, code: &str, level: i32, time: i64) {
+        sqlx::query(
+            "INSERT INTO messages (time, tool, code, location, text, level) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(time)
+        .bind(tool)
+        .bind(code)
+        .bind("test")
+        .bind(format!("{code} found in {tool}"))
+        .bind(level)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sql() {
+        let (client, _) = setup(ServerConfig::default()).await;
 
         // Sanity check
         let res = client.get("/invalid").send().await;
@@ -65,10 +198,3343 @@ mod tests {
                 .send()
                 .await;
             assert_eq!(res.status(), StatusCode::OK);
-            if res.json::<ApiResponse>().await.results.len() == 0 {
+            if res.json::<ApiResponse>().await.next.is_none() {
+                break;
+            }
+            page += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_requires_api_key() {
+        let (client, _) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            ..Default::default()
+        })
+        .await;
+
+        let res = client.post("/api/admin/regenerate-stats").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/api/admin/regenerate-stats")
+            .header("X-API-Key", "wrong")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // Admin endpoints are disabled without a configured key
+        let (client, _) = setup(ServerConfig::default()).await;
+        let res = client.post("/api/admin/regenerate-stats").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn critical_alert_hysteresis() {
+        use crate::alert::CriticalAlert;
+
+        let mut alert = CriticalAlert::new(100);
+        assert!(!alert.update(50));
+        assert!(alert.update(101));
+        // Only alert once per crossing
+        assert!(!alert.update(150));
+        // Dropping slightly below the threshold does not re-arm the alert
+        assert!(!alert.update(95));
+        assert!(!alert.update(101));
+        // Dropping to 90% does
+        assert!(!alert.update(90));
+        assert!(alert.update(101));
+    }
+
+    #[tokio::test]
+    async fn tool_score() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-score",
+            &[
+                ("URL_INVALID", 8),
+                ("URL_NO_SSL", 6),
+                ("URL_NO_SSL", 6),
+                ("EDAM_OBSOLETE", 7),
+            ],
+        )
+        .await;
+
+        let res = client.get("/api/tool/test-score/score").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let score = res.json::<ToolScore>().await;
+        assert_eq!(score.counts.critical, 1);
+        assert_eq!(score.counts.medium, 2);
+        assert_eq!(score.counts.low, 1);
+        assert_eq!(score.score, 10.0 + 2.0 * 2.0 + 1.0);
+
+        let res = client.get("/api/tool/bad$id/score").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let weights: ScoreWeights = "critical=100,low=0".parse().unwrap();
+        assert_eq!(weights.score(&score.counts), 100.0 + 2.0 * 2.0);
+        assert!("unknown=1".parse::<ScoreWeights>().is_err());
+    }
+
+    #[tokio::test]
+    async fn compare_tools() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-compare-a",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6)],
+        )
+        .await;
+        insert_messages(
+            &state.pool,
+            "test-compare-b",
+            &[("URL_NO_SSL", 6), ("EDAM_OBSOLETE", 7)],
+        )
+        .await;
+
+        let res = client
+            .get("/api/compare?a=test-compare-a&b=test-compare-b")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let comparison = res.json::<ToolComparison>().await;
+        assert_eq!(comparison.a.severity.high, 1);
+        assert_eq!(comparison.b.codes["EDAM_OBSOLETE"], 1);
+        assert_eq!(comparison.only_a, vec!["URL_INVALID"]);
+        assert_eq!(comparison.only_b, vec!["EDAM_OBSOLETE"]);
+
+        let res = client.get("/api/compare?a=test-compare-a&b=;").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn trending_tools() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let now = chrono::Utc::now().timestamp();
+        let day = 24 * 60 * 60;
+        delete_messages(&state.pool, "test-trending").await;
+        for time in [now, now - day, now - 2 * day, now - 10 * day] {
+            insert_message(&state.pool, "test-trending", "URL_INVALID", 5, time).await;
+        }
+
+        let res = client.get("/api/trending?window=7&limit=100").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let trending = res.json::<Vec<TrendingTool>>().await;
+        let tool = trending.iter().find(|x| x.tool == "test-trending").unwrap();
+        assert_eq!(tool.recent, 3);
+        assert_eq!(tool.prior, 1);
+        assert_eq!(tool.delta, 2);
+    }
+
+    #[tokio::test]
+    async fn search_post() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-search-post",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6)],
+        )
+        .await;
+
+        let get = client
+            .get("/api/search?query=test-search-post&code=URL_INVALID")
+            .send()
+            .await
+            .json::<ApiResponse>()
+            .await;
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({"query": "test-search-post", "code": "URL_INVALID"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let post = res.json::<ApiResponse>().await;
+        assert_eq!(post.count, 1);
+        assert_eq!(get.count, post.count);
+    }
+
+    #[tokio::test]
+    async fn versioned_stylesheet() {
+        let (client, _) = setup(ServerConfig::default()).await;
+        let url = style_url();
+
+        let res = client.get("/").send().await;
+        assert!(res.text().await.contains(&url));
+
+        let res = client.get(&url).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[axum::http::header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+    }
+
+    #[tokio::test]
+    async fn body_limit() {
+        let (client, _) = setup(ServerConfig {
+            body_limit: 16,
+            ..Default::default()
+        })
+        .await;
+
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({"query": "longer than sixteen bytes"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn csv_has_plain_text() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        delete_messages(&state.pool, "test-csv").await;
+        sqlx::query(
+            "INSERT INTO messages (time, tool, code, location, text, level) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(1_700_000_000_i64)
+        .bind("test-csv")
+        .bind("URL_INVALID")
+        .bind("test")
+        .bind("https://example.com is invalid")
+        .bind(5)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        let res = client.get("/api/download?query=test-csv").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let csv = res.text().await;
+        assert!(csv.contains(",https://example.com is invalid\n"));
+        assert!(!csv.contains("<a"));
+
+        let res = client.get("/api/search?query=test-csv").send().await;
+        let json = res.text().await;
+        assert!(json.contains("<a href=\\\"https://example.com\\\""));
+    }
+
+    #[tokio::test]
+    async fn raw_messages() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-raw<&>", &[("URL_INVALID", 5)]).await;
+
+        let processed = client
+            .get("/api/search?query=test-raw")
+            .send()
+            .await
+            .json::<serde_json::Value>()
+            .await;
+        assert_eq!(processed["results"][0]["tool"], "test-raw&lt;&amp;&gt;");
+        assert!(processed["results"][0]["html"].is_string());
+
+        let raw = client
+            .get("/api/search?query=test-raw&raw=true")
+            .send()
+            .await
+            .json::<serde_json::Value>()
+            .await;
+        assert_eq!(raw["results"][0]["tool"], "test-raw<&>");
+        assert!(raw["results"][0].get("html").is_none());
+
+        let csv = client
+            .get("/api/download?query=test-raw&raw=true")
+            .send()
+            .await
+            .text()
+            .await;
+        assert!(csv.contains(",test-raw<&>,"));
+    }
+
+    #[tokio::test]
+    async fn search_field() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-field-tool", &[("TEST_FIELD_CODE", 5)]).await;
+
+        for (field, query, count) in [
+            ("tool", "test-field-tool", 1),
+            ("tool", "TEST_FIELD_CODE", 0),
+            ("code", "TEST_FIELD_CODE", 1),
+            ("code", "test-field-tool", 0),
+            ("text", "TEST_FIELD_CODE found in test-field-tool", 1),
+            ("all", "TEST_FIELD_CODE found in test-field-tool", 0),
+            ("all", "test-field-tool", 1),
+            ("all", "TEST_FIELD_CODE", 1),
+        ] {
+            let res = client
+                .get(&format!("/api/search?query={query}&field={field}"))
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.json::<ApiResponse>().await.count,
+                count,
+                "{field} {query}"
+            );
+        }
+    }
+
+    #[test]
+    fn linter_exit_codes() {
+        use crate::api::linter_exit_status;
+
+        assert_eq!(linter_exit_status(Some(0)), StatusCode::OK);
+        // linter/cli.py could not find the tool on bio.tools
+        assert_eq!(linter_exit_status(Some(3)), StatusCode::NOT_FOUND);
+        assert_eq!(
+            linter_exit_status(Some(1)),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        // Killed by a signal
+        assert_eq!(linter_exit_status(None), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn timestamps() {
+        use crate::api::format_timestamp;
+
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13");
+        assert_eq!(format_timestamp(-60), "1969-12-31 23:59");
+        assert_eq!(format_timestamp(i64::MIN), "");
+
+        let message = Message::from(crate::db::DatabaseEntry {
+            time: -60,
+            tool: "test".to_owned(),
+            code: "URL_INVALID".to_owned(),
+            location: "test".to_owned(),
+            text: "test".to_owned(),
+            level: 5,
+        });
+        let message = serde_json::to_value(message).unwrap();
+        assert_eq!(message["timestamp"], "1969-12-31 23:59");
+    }
+
+    #[tokio::test]
+    async fn index_prefilled_search() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-index",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6)],
+        )
+        .await;
+
+        let res = client.get("/?q=samtools").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await.contains("data-search=\"samtools\""));
+
+        let res = client.get("/?q=test-index").send().await;
+        assert!(res
+            .text()
+            .await
+            .contains("<code>2</code> errors match <code>test-index</code>"));
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_json() {
+        let (client, _) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            body_limit: 16,
+            ..Default::default()
+        })
+        .await;
+
+        for (res, status) in [
+            (
+                client.get("/api/tool/bad$id/score").send().await,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                client.post("/api/admin/regenerate-stats").send().await,
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                client.get("/api/does-not-exist").send().await,
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                client.get("/api/search?page=invalid").send().await,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                client
+                    .post("/api/search")
+                    .json(&serde_json::json!({"query": "longer than sixteen bytes"}))
+                    .send()
+                    .await,
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+        ] {
+            assert_eq!(res.status(), status);
+            let body = res.json::<ApiErrorResponse>().await;
+            assert_eq!(body.error.status, status.as_u16());
+            assert!(!body.error.message.is_empty());
+        }
+
+        // Pages outside of the API are left alone
+        let res = client.get("/does-not-exist").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn documentation_not_modified() {
+        use axum::http::header;
+
+        let (client, _) = setup(ServerConfig::default()).await;
+
+        let res = client.get("/docs/URL_INVALID").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let last_modified = res.headers()[header::LAST_MODIFIED].clone();
+
+        let res = client
+            .get("/docs/URL_INVALID")
+            .header(header::IF_MODIFIED_SINCE, last_modified)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = client
+            .get("/docs/URL_INVALID")
+            .header(header::IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn tool_codes() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-codes",
+            &[("URL_NO_SSL", 7), ("URL_NO_SSL", 5), ("EDAM_OBSOLETE", 6)],
+        )
+        .await;
+
+        let res = client.get("/api/tool/test-codes/codes").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let codes = res.json::<Vec<ToolCode>>().await;
+        assert_eq!(codes.len(), 2);
+        assert_eq!(codes[0].code, "EDAM_OBSOLETE");
+        assert_eq!(codes[1].count, 2);
+        assert!(matches!(codes[1].max_severity, Severity::ReportHigh));
+
+        let res = client.get("/api/tool/bad$id/codes").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn tool_list() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-list-a", &[("URL_INVALID", 5)]).await;
+        insert_messages(&state.pool, "test-list-b", &[("URL_INVALID", 5)]).await;
+
+        let res = client.get("/api/tools?limit=1").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let first = res.json::<ToolList>().await;
+        assert!(first.count >= 2);
+        assert_eq!(first.results.len(), 1);
+        assert_eq!(first.next.as_deref(), Some("?page=1&limit=1"));
+        assert!(first.previous.is_none());
+
+        // Walk all pages, every tool is listed once
+        let mut tools = Vec::new();
+        let mut page = 0;
+        loop {
+            let list = client
+                .get(&format!("/api/tools?page={page}&limit=1000"))
+                .send()
+                .await
+                .json::<ToolList>()
+                .await;
+            tools.extend(list.results);
+            if list.next.is_none() {
                 break;
             }
             page += 1;
         }
+        assert_eq!(tools.len() as i64, first.count);
+        assert_eq!(
+            tools.iter().collect::<std::collections::HashSet<_>>().len(),
+            tools.len()
+        );
+        assert!(tools.contains(&"test-list-a".to_owned()));
+        assert!(tools.contains(&"test-list-b".to_owned()));
+    }
+
+    #[test]
+    fn openapi_operation_ids() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let mut ids = HashMap::new();
+        for (path, methods) in doc["paths"].as_object().unwrap() {
+            for (method, operation) in methods.as_object().unwrap() {
+                let id = operation["operationId"]
+                    .as_str()
+                    .unwrap_or_else(|| panic!("{method} {path} has no operation_id"));
+                assert!(
+                    ids.insert(id.to_owned(), path.clone()).is_none(),
+                    "Duplicate operation_id {id}"
+                );
+            }
+        }
+        assert_eq!(ids["searchMessages"], "/api/search");
+    }
+
+    #[tokio::test]
+    async fn csv_bom() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-bom", &[("URL_INVALID", 5)]).await;
+
+        let csv = client
+            .get("/api/download?query=test-bom&bom=true")
+            .send()
+            .await
+            .bytes()
+            .await;
+        assert!(csv.starts_with(b"\xEF\xBB\xBFtime,"));
+
+        let csv = client
+            .get("/api/download?query=test-bom")
+            .send()
+            .await
+            .bytes()
+            .await;
+        assert!(csv.starts_with(b"time,"));
+    }
+
+    #[tokio::test]
+    async fn csv_delimiter() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-delimiter", &[("URL_INVALID", 5)]).await;
+
+        let res = client
+            .get("/api/download?query=test-delimiter&delimiter=semicolon")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let csv = res.text().await;
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("time;timestamp;tool;code;severity;text"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(";test-delimiter;URL_INVALID;5;URL_INVALID found in test-delimiter"));
+
+        let res = client
+            .get("/api/download?query=test-delimiter&delimiter=pipe")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn last_relint() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        sqlx::query("DELETE FROM tool_relints WHERE tool = $1")
+            .bind("test-last-relint")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let res = client
+            .get("/api/tool/test-last-relint/last-relint")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let now = chrono::Utc::now().timestamp();
+        db::record_relint(&state.pool, "test-last-relint", now, 500)
+            .await
+            .unwrap();
+        db::record_relint(&state.pool, "test-last-relint", now, 200)
+            .await
+            .unwrap();
+
+        let res = client
+            .get("/api/tool/test-last-relint/last-relint")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let relint = res.json::<LastRelint>().await;
+        assert_eq!(relint.status, 200);
+        assert_eq!(relint.time, now);
+
+        let res = client
+            .get("/api/compare?a=test-last-relint&b=test-compare-b")
+            .send()
+            .await;
+        let comparison = res.json::<ToolComparison>().await;
+        assert_eq!(comparison.a.last_checked, Some(relint.time));
+    }
+
+    #[tokio::test]
+    async fn tool_messages_grouped() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-group",
+            &[("URL_NO_SSL", 6), ("EDAM_OBSOLETE", 7), ("URL_NO_SSL", 6)],
+        )
+        .await;
+        // Only the exact ID matches, unlike a search
+        insert_messages(&state.pool, "test-group-other", &[("URL_INVALID", 5)]).await;
+
+        let res = client.get("/api/tool/test-group").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let messages = res.json::<ToolMessages>().await;
+        assert_eq!(messages.tool, "test-group");
+        assert_eq!(messages.last_linted, Some(1_700_000_000));
+        assert_eq!(
+            messages.codes.keys().collect::<Vec<_>>(),
+            vec!["EDAM_OBSOLETE", "URL_NO_SSL"]
+        );
+        let group = &messages.codes["URL_NO_SSL"];
+        assert_eq!(group.count, 2);
+        assert_eq!(group.messages.len(), 2);
+        assert!(group.messages.iter().all(|x| x.code == "URL_NO_SSL"));
+        assert_eq!(messages.codes["EDAM_OBSOLETE"].count, 1);
+
+        let res = client.get("/api/tool/test-group-none").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let messages = res.json::<ToolMessages>().await;
+        assert_eq!(messages.last_linted, None);
+        assert!(messages.codes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_partial() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-partial<b>",
+            &[("URL_INVALID", 5), ("EDAM_OBSOLETE", 7)],
+        )
+        .await;
+
+        let res = client
+            .get("/api/search/partial?query=test-partial&field=tool")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get("X-Next-Page").is_none());
+        let html = res.text().await;
+        assert_eq!(html.matches("<tr>").count(), 2);
+        assert!(html.contains("href=\"/docs/URL_INVALID\""));
+        assert!(html.contains("test-partial&lt;b&gt;"));
+        assert!(!html.contains("<html"));
+    }
+
+    #[tokio::test]
+    async fn search_max_offset() {
+        let (client, _) = setup(ServerConfig {
+            max_search_offset: 200,
+            ..Default::default()
+        })
+        .await;
+
+        let res = client.get("/api/search?page=2").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.json::<ApiResponse>().await.next.is_none());
+
+        let res = client.get("/api/search?page=3").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let error = res.json::<ApiErrorResponse>().await.error;
+        assert!(error.message.contains("offset 200"));
+
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({"page": 1000000}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // Neither a negative offset nor an overflow reaches the database
+        for page in [-1, i64::MAX] {
+            let res = client.get(&format!("/api/search?page={page}")).send().await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        }
+        let res = client.get("/api/search?page=-1").send().await;
+        let error = res.json::<ApiErrorResponse>().await.error;
+        assert!(error.message.contains("numbered from 0"));
+    }
+
+    #[tokio::test]
+    async fn search_count_only() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-count-only",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6), ("URL_NO_SSL", 6)],
+        )
+        .await;
+
+        let res = client
+            .get("/api/search?query=test-count-only&count_only=true")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let json = res.json::<serde_json::Value>().await;
+        assert_eq!(json, serde_json::json!({"count": 3}));
+
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({"query": "test-count-only", "code": "URL_NO_SSL", "count_only": true}))
+            .send()
+            .await;
+        assert_eq!(res.json::<SearchCount>().await.count, 2);
+    }
+
+    #[tokio::test]
+    async fn last_relint_error() {
+        use crate::api::stderr_tail;
+
+        let (client, state) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            ..Default::default()
+        })
+        .await;
+
+        let stderr = (1..=60).map(|x| format!("line {x}\n")).collect::<String>();
+        let tail = stderr_tail(stderr.as_bytes());
+        assert!(tail.starts_with("line 11\n"));
+        assert!(tail.ends_with("line 60"));
+        // Invalid UTF-8 from the linter is replaced instead of failing the relint
+        assert_eq!(stderr_tail(b"bad \xff byte"), "bad \u{fffd} byte");
+
+        let now = chrono::Utc::now().timestamp();
+        db::record_relint_error(&state.pool, "test-relint-error", Some(1), &tail, now, 60)
+            .await
+            .unwrap();
+
+        let res = client
+            .get("/api/tool/test-relint-error/last-error")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .get("/api/tool/test-relint-error/last-error")
+            .header("X-API-Key", "secret")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let error = res.json::<RelintError>().await;
+        assert_eq!(error.exit_code, Some(1));
+        assert_eq!(error.stderr, tail);
+
+        // Expired failures are not returned
+        sqlx::query("UPDATE tool_relint_errors SET time = 1700000000 WHERE tool = $1")
+            .bind("test-relint-error")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let res = client
+            .get("/api/tool/test-relint-error/last-error")
+            .header("X-API-Key", "secret")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn statistics_fields() {
+        let (client, _) = setup(ServerConfig::default()).await;
+
+        let res = client
+            .get("/api/statistics?fields=time,total_errors")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let json = res.json::<serde_json::Value>().await;
+        let entries = json["data"].as_array().unwrap();
+        assert!(!entries.is_empty());
+        for entry in entries {
+            let keys: Vec<&String> = entry.as_object().unwrap().keys().collect();
+            assert_eq!(keys, ["time", "total_errors"]);
+        }
+
+        let res = client
+            .get("/api/statistics?fields=time,unknown")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn statistics_resolution() {
+        let (client, _) = setup(ServerConfig::default()).await;
+
+        let raw = client.get("/api/statistics").send().await;
+        let raw = raw.json::<Statistics>().await;
+
+        let res = client
+            .get("/api/statistics?resolution=month&fields=time,total_errors")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let json = res.json::<serde_json::Value>().await;
+        let entries = json["data"].as_array().unwrap();
+        assert!(entries.len() < raw.data.len());
+
+        // Buckets start on the first of the month and average their entries
+        let first_month: Vec<u64> = raw
+            .data
+            .iter()
+            .filter(|x| x.time < entries[1]["time"].as_u64().unwrap())
+            .map(|x| x.total_errors)
+            .collect();
+        let average = first_month.iter().sum::<u64>() as f64 / first_month.len() as f64;
+        assert_eq!(entries[0]["total_errors"], average.round() as u64);
+        let start = chrono::DateTime::from_timestamp(entries[0]["time"].as_i64().unwrap(), 0);
+        assert_eq!(start.unwrap().format("%d %H:%M").to_string(), "01 00:00");
+
+        let res = client.get("/api/statistics?resolution=year").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn download_client_disconnect() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        delete_messages(&state.pool, "test-download-disconnect").await;
+        sqlx::query(
+            "INSERT INTO messages (time, tool, code, location, text, level) SELECT 1700000000, $1, 'URL_INVALID', 'homepage', repeat('x', 1000), 5 FROM generate_series(1, 20000)",
+        )
+        .bind("test-download-disconnect")
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        let mut res = client
+            .get("/api/download?query=test-download-disconnect")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.chunk().await.is_some());
+        drop(res);
+
+        // The query is dropped with the response and its connection returned to the pool
+        let released = async {
+            while state.pool.num_idle() < state.pool.size() as usize {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(5), released)
+            .await
+            .unwrap();
+
+        delete_messages(&state.pool, "test-download-disconnect").await;
+    }
+
+    #[tokio::test]
+    async fn normalized_tool_ids() {
+        use crate::api::normalize_tool_id;
+
+        assert_eq!(normalize_tool_id(" SamTools "), "samtools");
+
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "Test-Normalize", &[("URL_INVALID", 5)]).await;
+
+        for id in ["test-normalize", "TEST-Normalize", "%20test-normalize%20"] {
+            let res = client.get(&format!("/api/tool/{id}")).send().await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let messages = res.json::<ToolMessages>().await;
+            assert_eq!(messages.tool, "test-normalize");
+            assert_eq!(messages.codes["URL_INVALID"].count, 1);
+
+            let res = client.get(&format!("/api/tool/{id}/codes")).send().await;
+            assert_eq!(res.json::<Vec<ToolCode>>().await.len(), 1);
+        }
+
+        let res = client
+            .get("/api/search?query=%20TEST-normalize%20&count_only=true")
+            .send()
+            .await;
+        assert_eq!(res.json::<SearchCount>().await.count, 1);
+
+        delete_messages(&state.pool, "Test-Normalize").await;
+    }
+
+    #[tokio::test]
+    async fn category_summary() {
+        use crate::api::code_category;
+
+        assert_eq!(code_category("URL_NO_SSL"), "URL");
+        assert_eq!(code_category("EDAM_OBSOLETE"), "EDAM");
+        assert_eq!(code_category("PMCID_BUT_NOT_DOI"), "DOI-PMID");
+        assert_eq!(code_category("NONE_OR_UNKNOWN"), "other");
+
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-categories",
+            &[("URL_NO_SSL", 6), ("DOI_BUT_NOT_PMID", 7)],
+        )
+        .await;
+
+        let res = client.get("/api/summary/categories").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let summary = res.json::<CategorySummary>().await;
+        assert_eq!(
+            summary.keys().collect::<Vec<_>>(),
+            ["DOI-PMID", "EDAM", "URL", "other"]
+        );
+        assert!(summary["URL"] >= 1);
+        assert!(summary["DOI-PMID"] >= 1);
+
+        // Served from the cache until the TTL expires
+        delete_messages(&state.pool, "test-categories").await;
+        let res = client.get("/api/summary/categories").send().await;
+        assert_eq!(res.json::<CategorySummary>().await, summary);
+    }
+
+    #[tokio::test]
+    async fn statistics_cached_bytes() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let cached_json = || {
+            let cached = state.statistics.read().unwrap();
+            cached.as_ref().unwrap().json.clone()
+        };
+
+        let first = client.get("/api/statistics").send().await.bytes().await;
+        let json = cached_json();
+        assert_eq!(first, json);
+
+        // Served from the same buffer without reading the file again
+        let second = client.get("/api/statistics").send().await.bytes().await;
+        assert_eq!(second, first);
+        assert_eq!(cached_json().as_ptr(), json.as_ptr());
+
+        // A newer file is read again
+        let file = std::fs::File::options()
+            .write(true)
+            .open(&state.stats_file_path)
+            .unwrap();
+        let modified = file.metadata().unwrap().modified().unwrap();
+        file.set_modified(modified + Duration::from_secs(1))
+            .unwrap();
+        client.get("/api/statistics").send().await;
+        file.set_modified(modified).unwrap();
+        assert_ne!(cached_json().as_ptr(), json.as_ptr());
+    }
+
+    #[tokio::test]
+    async fn severity_name_or_value() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-severity-param",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6), ("URL_TIMEOUT", 5)],
+        )
+        .await;
+
+        let mut counts = Vec::new();
+        for severity in ["5", "ReportHigh"] {
+            let res = client
+                .get(&format!(
+                    "/api/search?query=test-severity-param&severity={severity}"
+                ))
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            counts.push(res.json::<ApiResponse>().await.count);
+        }
+        for severity in [serde_json::json!(5), serde_json::json!("ReportHigh")] {
+            let res = client
+                .post("/api/search")
+                .json(&serde_json::json!({"query": "test-severity-param", "severity": severity}))
+                .send()
+                .await;
+            counts.push(res.json::<ApiResponse>().await.count);
+        }
+        assert_eq!(counts, [2, 2, 2, 2]);
+
+        let res = client
+            .get("/api/search?query=test-severity-param&severity=3")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn broken_templates() {
+        use crate::api::load_templates;
+
+        assert!(PARSED_TEMPLATES.is_ok());
+
+        let dir = std::env::temp_dir().join("biotools-linter-broken-templates");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.html"), "{% if %}").unwrap();
+
+        let error = load_templates(&format!("{}/*.html", dir.display())).unwrap_err();
+        assert!(error.contains("broken.html"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn index_failing_count() {
+        use crate::api::{index_count, INDEX_PLACEHOLDER};
+
+        assert_eq!(index_count("count", async { Ok(42) }).await, "42");
+
+        let failing = async { Err::<i64, _>(sqlx::Error::PoolTimedOut) };
+        assert_eq!(index_count("count", failing).await, INDEX_PLACEHOLDER);
+    }
+
+    #[tokio::test]
+    async fn snapshots() {
+        let (client, _) = setup(ServerConfig::default()).await;
+
+        let statistics = client.get("/api/statistics").send().await;
+        let statistics = statistics.json::<Statistics>().await;
+
+        let res = client.get("/api/snapshots").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let snapshots = res.json::<Vec<Snapshot>>().await;
+        assert_eq!(snapshots.len(), statistics.data.len());
+        for (snapshot, entry) in snapshots.iter().zip(&statistics.data) {
+            assert_eq!(snapshot.time, entry.time);
+            assert_eq!(snapshot.total_errors, entry.total_errors);
+            assert_eq!(snapshot.unique_tools, entry.unique_tools);
+        }
+    }
+
+    #[tokio::test]
+    async fn csv_sort() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-csv-sort";
+        delete_messages(&state.pool, tool).await;
+        insert_message(&state.pool, tool, "URL_NO_SSL", 7, 1_700_000_000).await;
+        insert_message(&state.pool, tool, "URL_INVALID", 8, 1_700_000_100).await;
+        insert_message(&state.pool, tool, "URL_TIMEOUT", 5, 1_700_000_200).await;
+        insert_message(&state.pool, tool, "URL_BAD_STATUS", 5, 1_700_000_300).await;
+
+        let codes = |csv: String| -> Vec<String> {
+            csv.lines()
+                .skip(1)
+                .map(|x| x.split(',').nth(3).unwrap().to_owned())
+                .collect()
+        };
+
+        let res = client
+            .get("/api/download?query=test-csv-sort&sort=severity_desc")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            codes(res.text().await),
+            ["URL_INVALID", "URL_BAD_STATUS", "URL_TIMEOUT", "URL_NO_SSL"]
+        );
+
+        let res = client.get("/api/download?query=test-csv-sort").send().await;
+        assert_eq!(
+            codes(res.text().await),
+            ["URL_BAD_STATUS", "URL_TIMEOUT", "URL_INVALID", "URL_NO_SSL"]
+        );
+
+        let res = client
+            .get("/api/download?query=test-csv-sort&sort=severity")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn tool_bundle() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-bundle",
+            &[("URL_NO_SSL", 6), ("URL_NO_SSL", 6), ("EDAM_OBSOLETE", 5)],
+        )
+        .await;
+        db::record_relint(
+            &state.pool,
+            "test-bundle",
+            chrono::Utc::now().timestamp(),
+            200,
+        )
+        .await
+        .unwrap();
+
+        let res = client.get("/api/tool/Test-Bundle/bundle").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let bundle = res.json::<ToolBundle>().await;
+        assert_eq!(bundle.tool, "test-bundle");
+        assert_eq!(bundle.severity.medium, 2);
+        assert_eq!(bundle.severity.high, 1);
+        assert_eq!(bundle.codes.len(), 2);
+        assert_eq!(bundle.last_relint.unwrap().status, 200);
+        assert_eq!(bundle.count, 3);
+        assert_eq!(bundle.results.len(), 3);
+
+        let res = client.get("/api/tool/bad$id/bundle").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn search_max_query_length() {
+        let (client, _) = setup(ServerConfig {
+            max_query_length: 8,
+            ..Default::default()
+        })
+        .await;
+
+        let res = client.get("/api/search?query=123456789").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // Surrounding whitespace does not count
+        let res = client
+            .get("/api/search?query=%20%2012345678%20")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({"query": "123456789"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // The search prefilled on the index page is limited too
+        let res = client.get("/?q=123456789").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client.get("/?q=12345678").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn legacy_severities() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-legacy-levels",
+            &[
+                ("URL_INVALID", 1),
+                ("URL_LINTER_ERROR", 2),
+                ("URL_NO_SSL", 8),
+            ],
+        )
+        .await;
+
+        // Legacy levels and critical findings are searched by default
+        let res = client
+            .get("/api/search?query=test-legacy-levels")
+            .send()
+            .await;
+        let mut levels: Vec<i64> = res.json::<serde_json::Value>().await["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["severity"].as_i64().unwrap())
+            .collect();
+        levels.sort();
+        assert_eq!(levels, [1, 2, 8]);
+
+        let res = client
+            .get("/api/search?query=test-legacy-levels&severity=Error")
+            .send()
+            .await;
+        assert_eq!(res.json::<ApiResponse>().await.count, 1);
+
+        let res = client
+            .get("/api/download?query=test-legacy-levels")
+            .send()
+            .await;
+        let csv = res.text().await;
+        let mut levels: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|x| x.split(',').nth(4).unwrap())
+            .collect();
+        levels.sort();
+        assert_eq!(levels, ["1", "2", "8"]);
+
+        let res = client
+            .get("/api/tool/test-legacy-levels/score")
+            .send()
+            .await;
+        let score = res.json::<ToolScore>().await;
+        assert_eq!(score.counts.error, 1);
+        assert_eq!(score.counts.linter_error, 1);
+        assert_eq!(score.counts.critical, 1);
+    }
+
+    #[tokio::test]
+    async fn http2_prior_knowledge() {
+        let (_, state) = setup(ServerConfig::default()).await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+
+        let client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let res = client
+            .get(format!("http://{addr}/robots.txt"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.version(), reqwest::Version::HTTP_2);
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // HTTP/1.1 keeps working on the same port
+        let res = reqwest::get(format!("http://{addr}/robots.txt"))
+            .await
+            .unwrap();
+        assert_eq!(res.version(), reqwest::Version::HTTP_11);
+    }
+
+    #[tokio::test]
+    async fn peer_ip_without_proxy() {
+        let echo = Router::new()
+            .route(
+                "/",
+                get(|headers: axum::http::HeaderMap| async move {
+                    headers["X-Real-IP"].to_str().unwrap().to_owned()
+                }),
+            )
+            .layer(middleware::from_fn(peer_ip_fallback));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(echo, listener, None));
+
+        let res = reqwest::get(format!("http://{addr}/")).await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "127.0.0.1");
+
+        // The address set by a proxy is kept
+        let res = reqwest::Client::new()
+            .get(format!("http://{addr}/"))
+            .header("X-Real-IP", "10.0.0.1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "10.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn error_code_stats() {
+        use crate::api::ErrorCodeStats;
+
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-errorcode-stats",
+            &[
+                ("URL_NO_SSL", 6),
+                ("URL_NO_SSL", 6),
+                ("NOT_A_KNOWN_CODE", 6),
+            ],
+        )
+        .await;
+
+        let res = client.get("/api/errorcodes/stats").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let stats = res.json::<Vec<ErrorCodeStats>>().await;
+        assert_eq!(stats.len(), 20);
+        assert!(stats.iter().all(|x| x.code != "NOT_A_KNOWN_CODE"));
+        let no_ssl = stats.iter().find(|x| x.code == "URL_NO_SSL").unwrap();
+        assert_eq!(no_ssl.category, "URL");
+        assert!(no_ssl.count >= 2);
+
+        // Served from the cache until the TTL expires
+        delete_messages(&state.pool, "test-errorcode-stats").await;
+        let res = client.get("/api/errorcodes/stats").send().await;
+        assert_eq!(res.json::<Vec<ErrorCodeStats>>().await, stats);
+    }
+
+    #[tokio::test]
+    async fn stable_pagination() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let codes: Vec<String> = (0..250).map(|i| format!("STABLE_{i:03}")).collect();
+        let findings: Vec<(&str, i32)> = codes.iter().map(|x| (x.as_str(), 6)).collect();
+        insert_messages(&state.pool, "test-stable-sort", &findings).await;
+
+        // Every finding shares the same time, pages must neither repeat nor skip any
+        for _ in 0..2 {
+            let mut seen = Vec::new();
+            for page in 0..3 {
+                let res = client
+                    .get(&format!("/api/search?query=test-stable-sort&page={page}"))
+                    .send()
+                    .await
+                    .json::<serde_json::Value>()
+                    .await;
+                for message in res["results"].as_array().unwrap() {
+                    seen.push(message["code"].as_str().unwrap().to_owned());
+                }
+            }
+            assert_eq!(seen, codes);
+        }
+
+        delete_messages(&state.pool, "test-stable-sort").await;
+    }
+
+    #[tokio::test]
+    async fn tool_url() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-tool-url", &[("URL_INVALID", 5)]).await;
+
+        let res = client
+            .get("/api/search?query=test-tool-url")
+            .send()
+            .await
+            .json::<serde_json::Value>()
+            .await;
+        assert_eq!(
+            res["results"][0]["tool_url"],
+            "https://bio.tools/test-tool-url"
+        );
+
+        let csv = client
+            .get("/api/download?query=test-tool-url&tool_url=true")
+            .send()
+            .await
+            .text()
+            .await;
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("time,timestamp,tool,tool_url,code,severity,text")
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .contains(",test-tool-url,https://bio.tools/test-tool-url,URL_INVALID,"));
+
+        // The column is opt-in
+        let csv = client
+            .get("/api/download?query=test-tool-url")
+            .send()
+            .await
+            .text()
+            .await;
+        assert!(!csv.contains("tool_url"));
+
+        // Another instance, set per server
+        let (client, _) = setup(ServerConfig {
+            tool_url_base: "https://biotools.example.org/tool/".to_owned(),
+            ..Default::default()
+        })
+        .await;
+        let res = client
+            .get("/api/tool/test-tool-url")
+            .send()
+            .await
+            .json::<ToolMessages>()
+            .await;
+        assert_eq!(
+            res.codes["URL_INVALID"].messages[0].tool_url,
+            "https://biotools.example.org/tool/test-tool-url"
+        );
+
+        delete_messages(&state.pool, "test-tool-url").await;
+    }
+
+    #[test]
+    fn statistics_unlisted_code() {
+        use crate::api::load_statistics;
+
+        let path = std::env::temp_dir().join("biotools-linter-unlisted-code.json");
+        std::fs::write(
+            &path,
+            r#"{"data": [
+                {"time": 1, "total_count_on_biotools": 1, "total_errors": 1, "unique_tools": 1, "error_types": {"URL_INVALID": 1}},
+                {"time": 2, "total_count_on_biotools": 1, "total_errors": 3, "unique_tools": 1, "error_types": {"URL_INVALID": 1, "NEW_LINTER_CODE": 2}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let statistics = load_statistics(&path);
+        std::fs::remove_file(path).unwrap();
+        let first = &statistics.data[0].error_types;
+        assert_eq!(first["NEW_LINTER_CODE"], serde_json::Value::Null);
+        assert_eq!(first["EDAM_INVALID"], serde_json::Value::Null);
+        assert_eq!(statistics.data[1].error_types["NEW_LINTER_CODE"], 2);
+    }
+
+    #[tokio::test]
+    async fn search_code_since() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        delete_messages(&state.pool, "test-code-since").await;
+        for (code, time) in [
+            ("EDAM_OBSOLETE", 1_700_000_000),
+            ("EDAM_OBSOLETE", 1_700_500_000),
+            ("URL_INVALID", 1_700_500_000),
+        ] {
+            insert_message(&state.pool, "test-code-since", code, 6, time).await;
+        }
+
+        for url in [
+            "/api/search?query=test-code-since&code=EDAM_OBSOLETE&since=1700400000",
+            "/api/search?query=test-code-since&code=edam_obsolete&since=1700400000",
+        ] {
+            let res = client
+                .get(url)
+                .send()
+                .await
+                .json::<serde_json::Value>()
+                .await;
+            assert_eq!(res["count"], 1);
+            assert_eq!(res["results"][0]["code"], "EDAM_OBSOLETE");
+            assert_eq!(res["results"][0]["time"], 1_700_500_000);
+        }
+
+        let res = client
+            .get("/api/search?query=test-code-since&since=1700400000&count_only=true")
+            .send()
+            .await
+            .json::<SearchCount>()
+            .await;
+        assert_eq!(res.count, 2);
+
+        delete_messages(&state.pool, "test-code-since").await;
+    }
+
+    #[tokio::test]
+    async fn search_code_since_uses_index() {
+        use crate::api::{CodeFilter, SeverityFilter, TimeRange};
+
+        let filter = CodeFilter::parse("edam*");
+        assert_eq!(filter.range(), ("EDAM".to_owned(), "EDAN".to_owned()));
+        let filter = CodeFilter::parse("URL*,EDAM*");
+        assert_eq!(filter.range(), ("EDAM".to_owned(), "URM".to_owned()));
+        let filter = CodeFilter::parse("*OBSOLETE");
+        assert_eq!(filter.range(), (String::new(), char::MAX.to_string()));
+
+        let (_, state) = setup(ServerConfig::default()).await;
+        let query = db::count_messages_query(
+            &SeverityFilter::default(),
+            &CodeFilter::parse("EDAM*"),
+            TimeRange {
+                since: 1_700_400_000,
+                until: i64::MAX,
+            },
+        );
+        let plan = generic_plan(&state.pool, query).await;
+        let condition = plan
+            .lines()
+            .find(|x| x.contains("Index Cond"))
+            .unwrap_or_else(|| panic!("no index condition in\n{plan}"));
+        assert!(plan.contains("messages_code_time"), "{plan}");
+        // Parameters rather than values, the plan is the generic one
+        assert!(condition.contains("code ~>=~ $6"), "{plan}");
+        assert!(condition.contains("\"time\" >= $4"), "{plan}");
+    }
+
+    #[tokio::test]
+    async fn search_cursor_uses_index() {
+        use crate::api::{
+            CodeFilter, SearchCursor, SearchOrder, SearchPage, SearchStart, SeverityFilter,
+            TimeRange,
+        };
+
+        let (_, state) = setup(ServerConfig::default()).await;
+        let query = db::messages_page_query(
+            SearchPage {
+                start: SearchStart::After(SearchCursor {
+                    time: 1_700_000_000,
+                    id: 1,
+                }),
+                order: SearchOrder::default(),
+            },
+            &SeverityFilter::default(),
+            &CodeFilter::parse(""),
+            TimeRange {
+                since: 0,
+                until: i64::MAX,
+            },
+        );
+        let plan = generic_plan(&state.pool, query).await;
+        // The index returns the page in order, from the cursor on
+        assert!(plan.contains("messages_time_id"), "{plan}");
+        assert!(!plan.contains("Sort"), "{plan}");
+    }
+
+    #[tokio::test]
+    async fn concurrent_relints_limited() {
+        use futures_util::future::join_all;
+
+        let (_, state) = setup(ServerConfig::default()).await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+
+        // A relint of test-busy-tool from 10.0.0.1 is in progress
+        state
+            .ips
+            .lock()
+            .unwrap()
+            .insert("10.0.0.1".to_owned(), "test-busy-tool".to_owned());
+
+        let client = reqwest::Client::new();
+        let relint = |ip: String, tool: &'static str| {
+            client
+                .post(format!("http://{addr}/api/lint?tool={tool}"))
+                .header("X-Real-IP", ip)
+                .send()
+        };
+        // A duplicate relint of the tool conflicts, a second relint from the IP is throttled
+        let same_tool = (0..5).map(|i| relint(format!("10.0.1.{i}"), "test-busy-tool"));
+        for res in join_all(same_tool).await {
+            assert_eq!(res.unwrap().status(), StatusCode::CONFLICT);
+        }
+        let same_ip = (0..5).map(|_| relint("10.0.0.1".to_owned(), "test-other-tool"));
+        for res in join_all(same_ip).await {
+            assert_eq!(res.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+        assert_eq!(state.relints_in_progress(), 1);
+
+        // Concurrent searches share the pool without growing past its limit
+        let searches = (0..20).map(|_| client.get(format!("http://{addr}/api/search")).send());
+        for res in join_all(searches).await {
+            assert_eq!(res.unwrap().status(), StatusCode::OK);
+        }
+        assert!(state.pool_connections() <= 5);
+    }
+
+    #[tokio::test]
+    async fn bulk_relint() {
+        use crate::api::BulkRelintResult;
+
+        let (_, state) = setup(ServerConfig::default()).await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+        let client = reqwest::Client::new();
+        let bulk = |ip: &'static str, body: &'static str| {
+            client
+                .post(format!("http://{addr}/api/lint/bulk"))
+                .header("X-Real-IP", ip)
+                .body(body)
+                .send()
+        };
+
+        // A relint of test-bulk-busy from another IP is in progress
+        state
+            .ips
+            .lock()
+            .unwrap()
+            .insert("10.0.3.9".to_owned(), "test-bulk-busy".to_owned());
+
+        // Invalid and busy tools are reported without stopping the batch, blank lines are skipped
+        let res = bulk("10.0.3.1", "a;b\n\n  TEST-BULK-BUSY \n--lint-all\n")
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[axum::http::header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        let results: Vec<BulkRelintResult> = res
+            .text()
+            .await
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let summary: Vec<_> = results
+            .iter()
+            .map(|x| (x.tool.as_str(), x.status, x.error.is_some()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a;b", 400, true),
+                ("test-bulk-busy", 409, true),
+                ("--lint-all", 400, true),
+            ]
+        );
+        assert_eq!(state.relints_in_progress(), 1);
+
+        // A second batch from an IP that is already linting is throttled
+        let res = bulk("10.0.3.9", "test-bulk-other").await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn fixed_clock() {
+        let (_, mut state) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            ..Default::default()
+        })
+        .await;
+        let now = 1_500_000_000;
+        state.clock = Arc::new(FixedClock(now));
+        let client = TestClient::new(app(&state));
+
+        // The windows are relative to the clock, not to the time the test runs
+        let day = 24 * 60 * 60;
+        delete_messages(&state.pool, "test-fixed-clock").await;
+        for time in [now - day, now - 2 * day, now - 10 * day] {
+            insert_message(&state.pool, "test-fixed-clock", "URL_INVALID", 5, time).await;
+        }
+        let res = client.get("/api/trending?window=7&limit=100").send().await;
+        let trending = res.json::<Vec<TrendingTool>>().await;
+        let tool = trending
+            .iter()
+            .find(|x| x.tool == "test-fixed-clock")
+            .unwrap();
+        assert_eq!((tool.recent, tool.prior), (2, 1));
+
+        // Expiry is measured from the clock, by wall clock time this failure is years old
+        db::record_relint_error(&state.pool, "test-fixed-clock", Some(1), "", now - day, 60)
+            .await
+            .unwrap();
+        let res = client
+            .get("/api/tool/test-fixed-clock/last-error")
+            .header("X-API-Key", "secret")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.json::<RelintError>().await.time, now - day);
+
+        delete_messages(&state.pool, "test-fixed-clock").await;
+    }
+
+    #[tokio::test]
+    async fn search_xml() {
+        use quick_xml::{events::Event, Reader};
+
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-xml<&>", &[("URL_INVALID", 5)]).await;
+
+        let res = client
+            .get("/api/search?query=test-xml&raw=true")
+            .header("Accept", "application/xml")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["Content-Type"], "application/xml");
+        let xml = res.text().await;
+
+        // Well-formed and the escaped values read back as stored
+        let mut reader = Reader::from_str(&xml);
+        let mut tools = Vec::new();
+        let mut in_tool = false;
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(x) => in_tool = x.name().as_ref() == b"tool",
+                Event::Text(x) if in_tool => tools.push(x.unescape().unwrap().into_owned()),
+                Event::End(_) => in_tool = false,
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+        assert_eq!(tools, ["test-xml<&>"]);
+
+        // JSON stays the default
+        let res = client.get("/api/search?query=test-xml").send().await;
+        assert!(res.headers()["Content-Type"]
+            .to_str()
+            .unwrap()
+            .starts_with("application/json"));
+
+        delete_messages(&state.pool, "test-xml<&>").await;
+    }
+
+    #[tokio::test]
+    async fn tools_messages_batch() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-batch-a",
+            &[("URL_INVALID", 5), ("EDAM_OBSOLETE", 6)],
+        )
+        .await;
+        insert_messages(&state.pool, "Test-Batch-B", &[("URL_NO_SSL", 6)]).await;
+        delete_messages(&state.pool, "test-batch-c").await;
+
+        let res = client
+            .post("/api/tools/messages")
+            .json(&["test-batch-a", "test-batch-b", "test-batch-c"])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let batch = res.json::<serde_json::Value>().await;
+        let codes = |tool: &str| {
+            batch[tool]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["code"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes("test-batch-a"), ["EDAM_OBSOLETE", "URL_INVALID"]);
+        assert_eq!(codes("test-batch-b"), ["URL_NO_SSL"]);
+        assert!(codes("test-batch-c").is_empty());
+
+        let res = client
+            .post("/api/tools/messages")
+            .json(&["test-batch-a", ";"])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = client
+            .post("/api/tools/messages")
+            .json(&vec!["test-batch-a"; 51])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        delete_messages(&state.pool, "test-batch-a").await;
+        delete_messages(&state.pool, "Test-Batch-B").await;
+    }
+
+    #[test]
+    fn redacted_linter_output() {
+        use crate::api::redacted_output;
+        use std::os::unix::process::ExitStatusExt;
+
+        let linter_dir = std::fs::canonicalize("../").unwrap();
+        let output = std::process::Output {
+            status: std::process::ExitStatus::from_raw(1 << 8),
+            stdout: Vec::new(),
+            stderr: format!("File \"{}/linter/cli.py\", line 1", linter_dir.display()).into_bytes(),
+        };
+
+        let line = redacted_output(&output);
+        assert!(line.contains("<linter>/linter/cli.py"));
+        assert!(!line.contains(&linter_dir.display().to_string()));
+    }
+
+    #[tokio::test]
+    async fn download_chunks() {
+        let (_, state) = setup(ServerConfig {
+            download_chunk_size: 100,
+            ..Default::default()
+        })
+        .await;
+        let codes: Vec<String> = (0..2000).map(|i| format!("CHUNK_{i:04}")).collect();
+        let findings: Vec<(&str, i32)> = codes.iter().map(|x| (x.as_str(), 6)).collect();
+        insert_messages(&state.pool, "test-download-chunks", &findings).await;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+
+        // Read chunk by chunk without holding the whole export
+        let mut res = reqwest::get(format!(
+            "http://{addr}/api/download?query=test-download-chunks"
+        ))
+        .await
+        .unwrap();
+        let (mut rows, mut largest) = (0, 0);
+        while let Some(chunk) = res.chunk().await.unwrap() {
+            rows += chunk.iter().filter(|x| **x == b'\n').count();
+            largest = largest.max(chunk.len());
+        }
+        assert_eq!(rows, 2001);
+        // A chunk of 100 rows is about 10 kB, far below the whole export
+        assert!(largest < 32 * 1024, "{largest}");
+
+        delete_messages(&state.pool, "test-download-chunks").await;
+    }
+
+    #[tokio::test]
+    async fn index_top_codes() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let findings = vec![("TEST_TOP_CODE", 6); 100];
+        insert_messages(&state.pool, "test-top-codes", &findings).await;
+
+        let top = db::top_codes(&state.pool, 5).await.unwrap();
+        assert_eq!(top[0], ("TEST_TOP_CODE".to_owned(), 100));
+
+        let res = client.get("/").send().await;
+        assert!(res
+            .text()
+            .await
+            .contains("<code>TEST_TOP_CODE</code> (100)"));
+
+        delete_messages(&state.pool, "test-top-codes").await;
+    }
+
+    #[tokio::test]
+    async fn data_metrics() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-data-metrics", &[("URL_INVALID", 8)]).await;
+
+        let res = client.get("/api/metrics/data").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers()["Content-Type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let metrics = res.text().await;
+
+        let value = |name: &str| -> i64 {
+            metrics
+                .lines()
+                .find_map(|x| x.strip_prefix(&format!("{name} ")))
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        assert!(metrics.contains("# TYPE biotools_linter_messages gauge"));
+        assert!(value("biotools_linter_messages") >= 1);
+        assert!(value("biotools_linter_critical_messages") >= 1);
+        assert!(value("biotools_linter_tools") >= 1);
+        assert!(value(r#"biotools_linter_messages_by_severity{severity="critical"}"#) >= 1);
+
+        // Served from the cache until the TTL expires
+        delete_messages(&state.pool, "test-data-metrics").await;
+        let res = client.get("/api/metrics/data").send().await;
+        assert_eq!(res.text().await, metrics);
+    }
+
+    #[tokio::test]
+    async fn version() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        api::refresh_linter_version(&state).await;
+
+        let res = client.get("/api/version").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let version = res.json::<serde_json::Value>().await;
+        assert_eq!(version["server"], env!("CARGO_PKG_VERSION"));
+
+        let head = std::process::Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .unwrap();
+        assert_eq!(
+            version["git_hash"],
+            String::from_utf8_lossy(&head.stdout).trim()
+        );
+
+        // The linter answers with the version declared in cli.py, unless it can't run here
+        let declared = include_str!("../../linter/cli.py")
+            .lines()
+            .find_map(|x| x.strip_prefix("__version__ = "))
+            .unwrap()
+            .trim_matches('"');
+        match version["linter"].as_str() {
+            Some(linter) => assert_eq!(linter, declared),
+            None => assert_eq!(api::linter_version(), None),
+        }
+    }
+
+    #[tokio::test]
+    async fn search_location() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        delete_messages(&state.pool, "test-location").await;
+        for location in ["publication[0]", "link[1]", "download_100%"] {
+            sqlx::query(
+                "INSERT INTO messages (time, tool, code, location, text, level) VALUES (1700000000, 'test-location', 'DOI_BUT_NOT_PMID', $1, 'text', 7)",
+            )
+            .bind(location)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        }
+
+        let count = |query: &'static str| {
+            let client = &client;
+            async move {
+                client
+                    .get(&format!(
+                        "/api/search?field=location&count_only=true&query={query}"
+                    ))
+                    .send()
+                    .await
+                    .json::<SearchCount>()
+                    .await
+                    .count
+            }
+        };
+        assert_eq!(count("publication").await, 1);
+        assert_eq!(count("[1]").await, 1);
+        // Wildcards are matched literally
+        assert_eq!(count("d_wnload").await, 0);
+        assert_eq!(count("100%25").await, 1);
+        assert_eq!(count("0%25").await, 1);
+
+        delete_messages(&state.pool, "test-location").await;
+    }
+
+    #[tokio::test]
+    async fn unknown_severity() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(&state.pool, "test-unknown-level", &[("URL_INVALID", 3)]).await;
+
+        let res = client
+            .get("/api/search?query=test-unknown-level")
+            .send()
+            .await
+            .json::<serde_json::Value>()
+            .await;
+        assert_eq!(res["results"][0]["severity"], 0);
+        assert_eq!(res["results"][0]["level"], 3);
+
+        let res = client
+            .get("/api/tool/test-unknown-level/score")
+            .send()
+            .await;
+        let score = res.json::<ToolScore>().await;
+        assert_eq!(score.counts.unknown, 1);
+        assert_eq!(score.counts.error, 0);
+
+        let csv = client
+            .get("/api/download?query=test-unknown-level")
+            .send()
+            .await
+            .text()
+            .await;
+        assert!(csv.contains(",URL_INVALID,3,"));
+
+        delete_messages(&state.pool, "test-unknown-level").await;
+    }
+
+    #[tokio::test]
+    async fn relint_dry_run() {
+        use crate::api::parse_preview;
+
+        let messages = parse_preview(
+            b"{\"time\": 1700000000, \"tool\": \"samtools\", \"code\": \"URL_INVALID\", \"location\": \"homepage\", \"text\": \"https://x is invalid\", \"level\": 5}\nnot json\n",
+        );
+        let messages = serde_json::to_value(messages).unwrap();
+        assert_eq!(messages.as_array().unwrap().len(), 1);
+        assert_eq!(messages[0]["code"], "URL_INVALID");
+        assert_eq!(messages[0]["severity"], 5);
+
+        let (_, state) = setup(ServerConfig::default()).await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+        let client = reqwest::Client::new();
+
+        let res = client
+            .post(format!("http://{addr}/api/lint?tool=;&dry_run=true"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // Whatever the linter answers, a dry run leaves no trace in the database
+        delete_messages(&state.pool, "test-dry-run").await;
+        let count = || async {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT (SELECT COUNT(*) FROM messages WHERE tool = 'test-dry-run') + (SELECT COUNT(*) FROM tool_relints WHERE tool = 'test-dry-run') + (SELECT COUNT(*) FROM tool_relint_errors WHERE tool = 'test-dry-run')",
+            )
+            .fetch_one(&state.pool)
+            .await
+            .unwrap()
+        };
+        client
+            .post(format!(
+                "http://{addr}/api/lint?tool=test-dry-run&dry_run=true"
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(count().await, 0);
+        assert_eq!(state.relints_in_progress(), 0);
+    }
+
+    #[tokio::test]
+    async fn search_head_total_count() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-head-count",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6), ("URL_NO_SSL", 6)],
+        )
+        .await;
+
+        let res = client
+            .head("/api/search?query=test-head-count&code=URL_NO_SSL")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["X-Total-Count"], "2");
+        assert!(res.bytes().await.is_empty());
+
+        let res = client.head("/api/search?page=999999").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn stalest_tools_first() {
+        let (_, state) = setup(ServerConfig::default()).await;
+        let tools = ["test-stale-a", "test-stale-b", "test-stale-c"];
+        for tool in tools {
+            delete_messages(&state.pool, tool).await;
+            sqlx::query("DELETE FROM tool_relints WHERE tool = $1")
+                .bind(tool)
+                .execute(&state.pool)
+                .await
+                .unwrap();
+        }
+
+        // a was checked first, c only has old findings but was relinted since
+        insert_message(&state.pool, "test-stale-a", "URL_INVALID", 5, 100).await;
+        insert_message(&state.pool, "test-stale-b", "URL_INVALID", 5, 200).await;
+        insert_message(&state.pool, "test-stale-c", "URL_INVALID", 5, 50).await;
+        db::record_relint(&state.pool, "test-stale-c", 300, 200)
+            .await
+            .unwrap();
+
+        let stalest = db::stalest_tools(&state.pool, i64::MAX).await;
+        let position = |tool| stalest.iter().position(|x| x == tool).unwrap();
+        assert!(position("test-stale-a") < position("test-stale-b"));
+        assert!(position("test-stale-b") < position("test-stale-c"));
+
+        assert_eq!(db::stalest_tools(&state.pool, 1).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn openapi_servers() {
+        let (client, _) = setup(ServerConfig {
+            base_url: "https://linter.example.org".to_owned(),
+            ..Default::default()
+        })
+        .await;
+
+        let doc = client
+            .get("/api/openapi.json")
+            .send()
+            .await
+            .json::<serde_json::Value>()
+            .await;
+        assert_eq!(
+            doc["servers"],
+            serde_json::json!([{"url": "https://linter.example.org"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn compare_csv() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-csv-a",
+            &[("URL_INVALID", 5), ("URL_NO_SSL", 6), ("URL_NO_SSL", 6)],
+        )
+        .await;
+        delete_messages(&state.pool, "test-csv-b").await;
+
+        let res = client
+            .post("/api/compare/csv")
+            .body("tool,code\nTest-CSV-a, url_no_ssl\ntest-csv-a,EDAM_OBSOLETE\ntest-csv-b,URL_INVALID\n")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let json = res.json::<serde_json::Value>().await;
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "found": [{"tool": "test-csv-a", "code": "URL_NO_SSL"}],
+                "missed": [
+                    {"tool": "test-csv-a", "code": "EDAM_OBSOLETE"},
+                    {"tool": "test-csv-b", "code": "URL_INVALID"},
+                ],
+                "extra": [{"tool": "test-csv-a", "code": "URL_INVALID"}],
+            })
+        );
+
+        for body in [
+            "name,code\ntest-csv-a,URL_INVALID\n",
+            "tool,code\ntest-csv-a\n",
+            "tool,code\n;rm -rf,URL_INVALID\n",
+        ] {
+            let res = client.post("/api/compare/csv").body(body).send().await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn statistics_snapshot() {
+        let (client, _) = setup(ServerConfig::default()).await;
+
+        let res = client.get("/api/statistics/1694024070").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let entry = res.json::<StatisticsEntry>().await;
+        assert_eq!(entry.time, 1694024070);
+        // Backfilled like the whole series
+        assert!(entry.error_types.contains_key("EDAM_OBSOLETE"));
+
+        let res = client.get("/api/statistics/1694024100").send().await;
+        assert_eq!(res.json::<StatisticsEntry>().await.time, 1694024070);
+
+        let res = client.get("/api/statistics/1800000000").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn log_sampling_keeps_errors() {
+        /// Log output collected by the test
+        #[derive(Clone, Default)]
+        struct Logs(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Logs {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // Only this server samples, other tests keep logging every request
+        let (client, _) = setup(ServerConfig {
+            log_sample_rate: 0.0,
+            ..Default::default()
+        })
+        .await;
+        let logs = Logs::default();
+        let writer = logs.clone();
+        // The test client serves on this thread, so the thread default sees its logs
+        let _guard = tracing::subscriber::set_default(
+            FmtSubscriber::builder()
+                .with_writer(move || writer.clone())
+                .finish(),
+        );
+
+        let ok = client.get("/api/version").send().await.status();
+        let failed = client.get("/api/tool/;ls").send().await.status();
+
+        assert_eq!(ok, StatusCode::OK);
+        assert_eq!(failed, StatusCode::BAD_REQUEST);
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(!logs.contains("API-VERSION"), "{logs}");
+        assert!(logs.contains("/api/tool/;ls FAILED 400"), "{logs}");
+    }
+
+    #[tokio::test]
+    async fn error_code_registry() {
+        let (client, state) = setup(ServerConfig::default()).await;
+
+        let res = client.get("/api/codes").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let codes = res.json::<Vec<serde_json::Value>>().await;
+        assert_eq!(codes.len(), 20);
+        assert_eq!(
+            codes[0],
+            serde_json::json!({
+                "code": "URL_INVALID",
+                "description": "A URL could not be parsed",
+                "severity": 5,
+                "documentation": "/docs/URL_INVALID",
+            })
+        );
+
+        // Every code links to an existing page
+        for code in &codes {
+            let url = code["documentation"].as_str().unwrap();
+            let res = client.get(url).send().await;
+            assert_eq!(res.status(), StatusCode::OK, "{url}");
+        }
+
+        // Templates link codes through the registry
+        insert_messages(&state.pool, "test-registry", &[("DOI_BUT_NOT_PMID", 6)]).await;
+        let html = client
+            .get("/api/search/partial?query=test-registry")
+            .send()
+            .await
+            .text()
+            .await;
+        assert!(
+            html.contains(r#"href="/docs/PublishingIDConversions""#),
+            "{html}"
+        );
+        let html = client.get("/").send().await.text().await;
+        assert!(html.contains(r#""DOI_BUT_NOT_PMID":"/docs/PublishingIDConversions""#));
+    }
+
+    #[tokio::test]
+    async fn search_cursor() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-cursor";
+        delete_messages(&state.pool, tool).await;
+        // Equal times make the id decide the order within a timestamp, spread over
+        // codes so none of them becomes one of the most common codes
+        for i in 0..250 {
+            let code = format!("TEST_CURSOR_{}", i % 4);
+            insert_message(&state.pool, tool, &code, 5, 1_700_000_000 + i / 3).await;
+        }
+
+        let mut seen = Vec::new();
+        let mut url = format!("/api/search?query={tool}&page=0");
+        loop {
+            let res = client.get(&url).send().await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let json = res.json::<serde_json::Value>().await;
+            assert_eq!(json["count"], 250);
+            seen.extend(json["results"].as_array().unwrap().iter().cloned());
+            match json["next_cursor"].as_str() {
+                Some(cursor) => {
+                    assert!(json["next"].is_null() || seen.len() == 100);
+                    url = format!("/api/search?query={tool}&cursor={cursor}");
+                }
+                None => break,
+            }
+        }
+
+        // Same rows in the same order as offset pagination
+        let mut paged = Vec::new();
+        for page in 0..3 {
+            let res = client
+                .get(&format!("/api/search?query={tool}&page={page}"))
+                .send()
+                .await;
+            let json = res.json::<serde_json::Value>().await;
+            paged.extend(json["results"].as_array().unwrap().iter().cloned());
+        }
+        assert_eq!(seen.len(), 250);
+        assert_eq!(seen, paged);
+
+        let res = client.get("/api/search?cursor=nope").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        delete_messages(&state.pool, tool).await;
+    }
+
+    #[tokio::test]
+    async fn search_sort() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-search-sort";
+        delete_messages(&state.pool, tool).await;
+        insert_message(&state.pool, tool, "URL_NO_SSL", 6, 1_700_000_200).await;
+        insert_message(&state.pool, tool, "EDAM_OBSOLETE", 8, 1_700_000_000).await;
+        insert_message(&state.pool, tool, "URL_INVALID", 7, 1_700_000_100).await;
+
+        let codes = |url: String| {
+            let client = &client;
+            async move {
+                let res = client.get(&url).send().await;
+                assert_eq!(res.status(), StatusCode::OK, "{url}");
+                let json = res.json::<serde_json::Value>().await;
+                json["results"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|x| x["code"].as_str().unwrap().to_owned())
+                    .collect::<Vec<_>>()
+            }
+        };
+        let search = format!("/api/search?query={tool}&field=tool");
+
+        assert_eq!(
+            codes(search.clone()).await,
+            ["URL_NO_SSL", "URL_INVALID", "EDAM_OBSOLETE"]
+        );
+        assert_eq!(
+            codes(format!("{search}&sort=time&order=asc")).await,
+            ["EDAM_OBSOLETE", "URL_INVALID", "URL_NO_SSL"]
+        );
+        assert_eq!(
+            codes(format!("{search}&sort=severity")).await,
+            ["EDAM_OBSOLETE", "URL_NO_SSL", "URL_INVALID"]
+        );
+        assert_eq!(
+            codes(format!("{search}&sort=code")).await,
+            ["EDAM_OBSOLETE", "URL_INVALID", "URL_NO_SSL"]
+        );
+        assert_eq!(
+            codes(format!("{search}&sort=code&order=desc")).await,
+            ["URL_NO_SSL", "URL_INVALID", "EDAM_OBSOLETE"]
+        );
+
+        let res = client.get("/api/search?sort=tool&cursor=1.1").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client.get("/api/search?sort=size").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn search_time_range() {
+        use crate::api::parse_timestamp;
+
+        assert_eq!(parse_timestamp("1700000000"), Some(1_700_000_000));
+        assert_eq!(parse_timestamp("2023-11-14"), Some(1_699_920_000));
+        assert_eq!(parse_timestamp("2023-11-14T22:13:20"), Some(1_700_000_000));
+        assert_eq!(
+            parse_timestamp("2023-11-15T00:13:20+02:00"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-time-range";
+        delete_messages(&state.pool, tool).await;
+        for (code, time) in [
+            ("URL_INVALID", 1_699_900_000),
+            ("URL_NO_SSL", 1_700_000_000),
+            ("EDAM_OBSOLETE", 1_700_100_000),
+        ] {
+            insert_message(&state.pool, tool, code, 5, time).await;
+        }
+
+        for (range, count) in [
+            ("since=1700000000&until=1700000000", 1),
+            ("since=2023-11-14", 2),
+            ("until=2023-11-14T22:13:20Z", 2),
+            ("since=2023-11-14&until=2023-11-15", 1),
+        ] {
+            let res = client
+                .get(&format!("/api/search?query={tool}&{range}"))
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::OK, "{range}");
+            let json = res.json::<serde_json::Value>().await;
+            assert_eq!(json["count"], count, "{range}");
+            assert_eq!(json["results"].as_array().unwrap().len(), count, "{range}");
+        }
+
+        let res = client
+            .post("/api/search")
+            .json(
+                &serde_json::json!({"query": tool, "since": 1_700_000_000, "until": "2023-11-15"}),
+            )
+            .send()
+            .await;
+        assert_eq!(res.json::<serde_json::Value>().await["count"], 1);
+
+        let csv = client
+            .get(&format!(
+                "/api/download?query={tool}&since=2023-11-14&until=2023-11-15"
+            ))
+            .send()
+            .await
+            .text()
+            .await;
+        assert_eq!(csv.lines().count(), 2, "{csv}");
+        assert!(csv.contains("URL_NO_SSL"));
+
+        let res = client.get("/api/search?since=yesterday").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn search_severity_code_lists() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-filter-lists";
+        delete_messages(&state.pool, tool).await;
+        insert_messages(
+            &state.pool,
+            tool,
+            &[
+                ("URL_INVALID", 5),
+                ("URL_NO_SSL", 6),
+                ("URL_LINTER_ERROR", 2),
+                ("EDAM_OBSOLETE", 6),
+                ("EDAM_NOT_RECOMMENDED", 7),
+            ],
+        )
+        .await;
+
+        for (filter, count) in [
+            ("severity=ReportHigh,ReportMedium", 3),
+            ("severity=5,7", 2),
+            ("severity=!LinterError", 4),
+            ("severity=ReportMedium,ReportLow,!ReportLow", 2),
+            ("code=URL_INVALID,edam_obsolete", 2),
+            ("code=!URL_*", 2),
+            ("code=URL_*,!URL_LINTER_ERROR", 2),
+            ("code=URL_*&severity=!LinterError", 2),
+        ] {
+            let res = client
+                .get(&format!("/api/search?query={tool}&{filter}"))
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::OK, "{filter}");
+            assert_eq!(
+                res.json::<serde_json::Value>().await["count"],
+                count,
+                "{filter}"
+            );
+        }
+
+        let res = client
+            .post("/api/search")
+            .json(&serde_json::json!({"query": tool, "severity": 6}))
+            .send()
+            .await;
+        assert_eq!(res.json::<serde_json::Value>().await["count"], 2);
+
+        let res = client
+            .get(&format!(
+                "/api/search?query={tool}&severity=ReportHigh,Bogus"
+            ))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn csv_quoting() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        delete_messages(&state.pool, "test-csv-quoting").await;
+        let text = "Says \"hi\", then\nleaves";
+        sqlx::query(
+            "INSERT INTO messages (time, tool, code, location, text, level) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(1_700_000_000_i64)
+        .bind("test-csv-quoting")
+        .bind("URL_INVALID")
+        .bind("test")
+        .bind(text)
+        .bind(5)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        let csv = client
+            .get("/api/download?query=test-csv-quoting&raw=true")
+            .send()
+            .await
+            .text()
+            .await;
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 1, "{csv}");
+        assert_eq!(&records[0][2], "test-csv-quoting");
+        assert_eq!(&records[0][5], text);
+    }
+
+    #[tokio::test]
+    async fn download_formats() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-download-format";
+        delete_messages(&state.pool, tool).await;
+        insert_messages(
+            &state.pool,
+            tool,
+            &[("URL_INVALID", 5), ("EDAM_OBSOLETE", 6)],
+        )
+        .await;
+
+        let res = client
+            .get(&format!("/api/download?query={tool}&format=json"))
+            .send()
+            .await;
+        assert_eq!(res.headers()["content-type"], "application/json");
+        let json = res.json::<serde_json::Value>().await;
+        let results = json.as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["tool"], tool);
+        assert!(results[0]["level"].is_i64());
+
+        let res = client
+            .get(&format!("/api/download?query={tool}&format=ndjson"))
+            .send()
+            .await;
+        assert_eq!(res.headers()["content-type"], "application/x-ndjson");
+        let text = res.text().await;
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|x| serde_json::from_str(x).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["code"], "EDAM_OBSOLETE");
+
+        let res = client
+            .get("/api/download?query=test-no-such-tool&format=json")
+            .send()
+            .await;
+        assert_eq!(res.json::<serde_json::Value>().await, serde_json::json!([]));
+
+        let res = client
+            .get(&format!("/api/download?query={tool}&format=xlsx"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn tool_messages_sarif() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-sarif";
+        delete_messages(&state.pool, tool).await;
+        insert_messages(
+            &state.pool,
+            tool,
+            &[("URL_INVALID", 5), ("EDAM_OBSOLETE", 6), ("URL_INVALID", 5)],
+        )
+        .await;
+
+        let res = client
+            .get(&format!("/api/tool/{tool}?format=sarif"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "application/sarif+json");
+        let sarif = res.json::<serde_json::Value>().await;
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "EDAM_OBSOLETE");
+        assert_eq!(rules[0]["defaultConfiguration"]["level"], "warning");
+        assert!(rules[1]["helpUri"]
+            .as_str()
+            .unwrap()
+            .ends_with("/docs/URL_INVALID"));
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1]["ruleId"], "URL_INVALID");
+        assert_eq!(results[1]["ruleIndex"], 1);
+        assert_eq!(results[1]["level"], "error");
+        assert_eq!(
+            results[1]["locations"][0]["logicalLocations"][0]["name"],
+            tool
+        );
+    }
+
+    #[tokio::test]
+    async fn webhook_delivery() {
+        let (_, mut state) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            ..Default::default()
+        })
+        .await;
+        let now = 1_800_000_000;
+        state.clock = Arc::new(FixedClock(now));
+        let client = TestClient::new(app(&state));
+        let tool = "test-webhook";
+        delete_messages(&state.pool, tool).await;
+        sqlx::query("DELETE FROM webhooks WHERE tool = $1")
+            .bind(tool)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        // Receives the deliveries
+        type Deliveries = Arc<Mutex<Vec<(String, axum::body::Bytes)>>>;
+        let received: Deliveries = Arc::default();
+        let receiver = Router::new()
+            .route(
+                "/",
+                post(
+                    |axum::extract::State(received): axum::extract::State<Deliveries>,
+                     headers: axum::http::HeaderMap,
+                     body: axum::body::Bytes| async move {
+                        let signature = headers[webhook::SIGNATURE_HEADER].to_str().unwrap();
+                        received.lock().unwrap().push((signature.to_owned(), body));
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(serve(receiver, listener, None));
+
+        let request = serde_json::json!({"url": url, "tool": " Test-Webhook "});
+        let res = client.post("/api/webhooks").json(&request).send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        for invalid in [
+            serde_json::json!({"url": "ftp://example.com", "tool": tool}),
+            serde_json::json!({"url": url}),
+            serde_json::json!({"url": url, "tool": "not a tool"}),
+        ] {
+            let res = client
+                .post("/api/webhooks")
+                .header("X-API-Key", "secret")
+                .json(&invalid)
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{invalid}");
+        }
+
+        let res = client
+            .post("/api/webhooks")
+            .header("X-API-Key", "secret")
+            .json(&request)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let webhook = res.json::<Webhook>().await;
+        assert_eq!(webhook.tool.as_deref(), Some(tool));
+
+        // Only findings found after the registration are delivered
+        let http = reqwest::Client::new();
+        let deliver =
+            |now| webhook::deliver_pending(&state.pool, &http, api::DEFAULT_TOOL_URL_BASE, now);
+        insert_message(&state.pool, tool, "URL_INVALID", 5, now - 1).await;
+        deliver(now + webhook::WEBHOOK_SAFETY_LAG).await;
+        assert!(received.lock().unwrap().is_empty());
+
+        // The finding with the lower ID commits last, it is not skipped by the one committed first
+        let found = now + 10;
+        let mut transaction = state.pool.begin().await.unwrap();
+        sqlx::query(
+            "INSERT INTO messages (time, tool, code, location, text, level) VALUES ($1, $2, 'URL_INVALID', 'test', 'late', 5)",
+        )
+        .bind(found)
+        .bind(tool)
+        .execute(&mut *transaction)
+        .await
+        .unwrap();
+        insert_message(&state.pool, tool, "EDAM_OBSOLETE", 6, found).await;
+        deliver(found + webhook::WEBHOOK_SAFETY_LAG - 1).await;
+        assert!(received.lock().unwrap().is_empty());
+        transaction.commit().await.unwrap();
+
+        deliver(found + webhook::WEBHOOK_SAFETY_LAG).await;
+        {
+            let received = received.lock().unwrap();
+            assert_eq!(received.len(), 1);
+            let (signature, body) = &received[0];
+            assert_eq!(*signature, webhook::sign(&webhook.secret, body));
+            let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+            assert_eq!(payload["subscription"], webhook.id);
+            assert_eq!(payload["messages"].as_array().unwrap().len(), 2);
+            assert_eq!(payload["messages"][0]["code"], "URL_INVALID");
+        }
+
+        // Delivered findings are not sent again
+        deliver(found + webhook::WEBHOOK_SAFETY_LAG).await;
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let path = format!("/api/webhooks/{}", webhook.id);
+        let res = client
+            .delete(&path)
+            .header("X-API-Key", "secret")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let res = client
+            .delete(&path)
+            .header("X-API-Key", "secret")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        delete_messages(&state.pool, tool).await;
+    }
+
+    #[tokio::test]
+    async fn relints_wait_for_lint_workers() {
+        let (_, state) = setup(ServerConfig::default()).await;
+
+        // Queued jobs are using every linter, a bulk or scheduled relint waits for one of them
+        let permits = state.linters.clone().acquire_many_owned(2).await.unwrap();
+        let relint = api::run_relint(&state, "test-lint-workers".to_owned(), |_| {});
+        assert!(tokio::time::timeout(Duration::from_millis(300), relint)
+            .await
+            .is_err());
+        drop(permits);
+        assert_eq!(state.linters.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn queued_relint() {
+        let (_, state) = setup(ServerConfig::default()).await;
+        sqlx::query("DELETE FROM lint_jobs WHERE tool LIKE 'test-queued-%'")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+
+        let client = reqwest::Client::new();
+        let relint = |ip: &'static str, tool: &'static str| {
+            client
+                .post(format!("http://{addr}/api/lint?tool={tool}"))
+                .header("X-Real-IP", ip)
+                .send()
+        };
+
+        let res = relint("10.0.2.1", "test-queued-relint").await.unwrap();
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        let job = res.json::<LintJob>().await.unwrap();
+        assert_eq!(job.status, "queued");
+        assert_eq!(job.result, None);
+
+        // The pending job conflicts like a running relint, and nothing holds a slot yet
+        let res = relint("10.0.2.2", "test-queued-relint").await.unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let res = relint("10.0.2.1", "test-queued-other").await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(state.relints_in_progress(), 0);
+
+        assert!(jobs::run_next(&state).await);
+        assert_eq!(state.relints_in_progress(), 0);
+
+        let job = client
+            .get(format!("http://{addr}/api/lint/status/{}", job.id))
+            .send()
+            .await
+            .unwrap()
+            .json::<LintJob>()
+            .await
+            .unwrap();
+        // The linter can't reach bio.tools in tests, either way the job ended
+        assert!(
+            ["finished", "failed"].contains(&job.status.as_str()),
+            "{job:?}"
+        );
+        assert_eq!(job.status == "finished", job.result == Some(200));
+        assert_eq!(job.status == "finished", job.messages.is_some());
+        assert!(job.started.is_some());
+        assert!(job.finished >= job.started);
+
+        // Once done the tool and the IP can queue again
+        let res = relint("10.0.2.1", "test-queued-relint").await.unwrap();
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        sqlx::query("DELETE FROM lint_jobs WHERE tool LIKE 'test-queued-%'")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let res = client
+            .get(format!("http://{addr}/api/lint/status/0"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn lint_output_stream() {
+        let (_, state) = setup(ServerConfig::default()).await;
+        sqlx::query("DELETE FROM lint_jobs WHERE tool = 'test-lint-stream'")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+
+        let client = reqwest::Client::new();
+        let job = client
+            .post(format!("http://{addr}/api/lint?tool=test-lint-stream"))
+            .header("X-Real-IP", "10.0.3.1")
+            .send()
+            .await
+            .unwrap()
+            .json::<LintJob>()
+            .await
+            .unwrap();
+
+        // Connected while the job is queued, the stream follows it to the end
+        let res = client
+            .get(format!("http://{addr}/api/lint/stream/{}", job.id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "text/event-stream");
+        assert!(jobs::run_next(&state).await);
+        let events = res.text().await.unwrap();
+        let (_, done) = events.rsplit_once("event: done\ndata: ").unwrap();
+        let done: LintJob = serde_json::from_str(done.trim_end()).unwrap();
+        assert_eq!(done.id, job.id);
+        assert!(["finished", "failed"].contains(&done.status.as_str()));
+        assert!(state.lint_output.lock().unwrap().is_empty());
+
+        // A finished job only reports how it ended
+        let events = client
+            .get(format!("http://{addr}/api/lint/stream/{}", job.id))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(events.starts_with("event: done\n"), "{events}");
+
+        let res = client
+            .get(format!("http://{addr}/api/lint/stream/0"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        sqlx::query("DELETE FROM lint_jobs WHERE tool = 'test-lint-stream'")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn lint_entry_urls() {
+        let (client, _) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            ..Default::default()
+        })
+        .await;
+
+        // A plain HTTP website, so every URL also lacks SSL
+        let website = Router::new().route("/ok", get(|| async { "ok" })).route(
+            "/moved",
+            get(|| async { axum::response::Redirect::permanent("/ok") }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(website, listener, None));
+
+        let entry = serde_json::json!({
+            "biotoolsID": "test-lint-urls",
+            "description": "Not a URL",
+            "homepage": format!("{base}/ok"),
+            "link": [{"url": format!("{base}/moved")}, {"url": format!("{base}/missing")}],
+            "download": [{"url": "ftp://example.com/file"}, {"url": "not a\u{200b}url"}],
+        });
+        let res = client.post("/api/lint/urls").json(&entry).send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/api/lint/urls")
+            .header("X-API-Key", "secret")
+            .json(&entry)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let findings: Vec<serde_json::Value> = res.json().await;
+        let mut found: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|x| (x["location"].as_str().unwrap(), x["code"].as_str().unwrap()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                ("download/1/url", "URL_INVALID"),
+                ("homepage", "URL_NO_SSL"),
+                ("link/0/url", "URL_NO_SSL"),
+                ("link/0/url", "URL_PERMANENT_REDIRECT"),
+                ("link/1/url", "URL_BAD_STATUS"),
+                ("link/1/url", "URL_NO_SSL"),
+            ]
+        );
+        let redirect = findings
+            .iter()
+            .find(|x| x["code"] == "URL_PERMANENT_REDIRECT")
+            .unwrap();
+        assert_eq!(redirect["severity"], 7);
+        assert_eq!(
+            redirect["text"],
+            format!("URL {base}/moved at link/0/url returns a redirect.")
+        );
+
+        // A checked URL is reported at its new location
+        let findings = linter::urls::check("credit/0/url", &format!("{base}/missing")).await;
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|x| x.location == "credit/0/url"));
+    }
+
+    #[tokio::test]
+    async fn edam_validate() {
+        let (client, mut state) = setup(ServerConfig::default()).await;
+        let res = client
+            .post("/api/edam/validate")
+            .json(&["operation_0324"])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Class ID, label, obsolete, obsolete comment and not recommended in the columns of EDAM.csv
+        let row = |id: &str, label: &str, obsolete: &str, comment: &str, not_recommended: &str| {
+            let mut columns = vec![""; 76];
+            columns[0] = id;
+            columns[1] = label;
+            columns[4] = obsolete;
+            columns[11] = comment;
+            columns[75] = not_recommended;
+            columns.join(",") + "\n"
+        };
+        let csv = row("Class ID", "Preferred Label", "Obsolete", "", "")
+            + &row(
+                "http://edamontology.org/operation_0324",
+                "Phylogenetic tree analysis",
+                "FALSE",
+                "",
+                "",
+            )
+            + &row(
+                "http://edamontology.org/topic_0091",
+                "Bioinformatics",
+                "TRUE",
+                "Use topic_3307",
+                "",
+            )
+            + &row(
+                "http://edamontology.org/data_0006",
+                "Data",
+                "FALSE",
+                "",
+                "TRUE",
+            );
+        let edam = linter::edam::Edam::from_reader(csv.as_bytes()).unwrap();
+        assert_eq!(edam.class_count(), 3);
+        state.edam = Some(Arc::new(edam));
+        let client = TestClient::new(app(&state));
+
+        let res = client
+            .post("/api/edam/validate")
+            .json(&[
+                "http://edamontology.org/operation_0324",
+                " topic_0091 ",
+                "data_0006",
+                "operation_9999",
+            ])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let findings: Vec<serde_json::Value> = res.json().await;
+        let found: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|x| (x["location"].as_str().unwrap(), x["code"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("1", "EDAM_OBSOLETE"),
+                ("2", "EDAM_NOT_RECOMMENDED"),
+                ("3", "EDAM_INVALID")
+            ]
+        );
+        assert_eq!(
+            findings[0]["text"],
+            "EDAM \"Bioinformatics\" at 1 is obsolete. (Use topic_3307)"
+        );
+        assert_eq!(findings[1]["severity"], 7);
+    }
+
+    #[tokio::test]
+    async fn lint_entry_publications() {
+        // Stands in for the NCBI ID converter and counts the lookups
+        type Lookups = Arc<Mutex<Vec<String>>>;
+        let lookups: Lookups = Arc::default();
+        let idconv = Router::new()
+            .route(
+                "/",
+                get(
+                    |axum::extract::State(lookups): axum::extract::State<Lookups>,
+                     axum::extract::Query(query): axum::extract::Query<HashMap<String, String>>| async move {
+                        lookups.lock().unwrap().push(query["ids"].clone());
+                        axum::Json(match query["ids"].as_str() {
+                            "10.1000/test-lint-doi" => serde_json::json!({"status": "ok", "records": [
+                                {"doi": "10.1000/test-lint-doi", "pmid": "123", "pmcid": "PMC123"}
+                            ]}),
+                            "456" => serde_json::json!({"status": "ok", "records": [
+                                {"doi": "10.1000/test-lint-pmid", "pmid": 456}
+                            ]}),
+                            _ => serde_json::json!({"status": "error", "message": "invalid article id"}),
+                        })
+                    },
+                ),
+            )
+            .with_state(lookups.clone());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let idconv_url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(serve(idconv, listener, None));
+
+        let (client, _) = setup(ServerConfig {
+            api_key: Some("secret".to_owned()),
+            idconv_url,
+            ..Default::default()
+        })
+        .await;
+        let entry = serde_json::json!({
+            "biotoolsID": "test-lint-publications",
+            "publication": [
+                {"doi": "10.1000/test-lint-doi", "pmid": null, "pmcid": ""},
+                {"doi": null, "pmid": "456", "pmcid": null},
+                {"doi": "10.1000/test-lint-unknown", "pmid": null, "pmcid": null},
+                {"doi": null, "pmid": null, "pmcid": null},
+            ],
+        });
+        let res = client
+            .post("/api/lint/publications")
+            .json(&entry)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        for _ in 0..2 {
+            let res = client
+                .post("/api/lint/publications")
+                .header("X-API-Key", "secret")
+                .json(&entry)
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let findings: Vec<serde_json::Value> = res.json().await;
+            let found: Vec<(&str, &str)> = findings
+                .iter()
+                .map(|x| (x["location"].as_str().unwrap(), x["code"].as_str().unwrap()))
+                .collect();
+            assert_eq!(
+                found,
+                [
+                    ("publication/0", "DOI_BUT_NOT_PMID"),
+                    ("publication/0", "DOI_BUT_NOT_PMCID"),
+                    ("publication/1", "PMID_BUT_NOT_DOI"),
+                ]
+            );
+            assert_eq!(
+                findings[2]["text"],
+                "Publication PMID 456 (https://pubmed.ncbi.nlm.nih.gov/456) does not have a DOI in the database."
+            );
+        }
+
+        // Known publications are looked up once, unknown ones every time
+        let mut lookups = lookups.lock().unwrap().clone();
+        lookups.sort();
+        assert_eq!(
+            lookups,
+            [
+                "10.1000/test-lint-doi",
+                "10.1000/test-lint-unknown",
+                "10.1000/test-lint-unknown",
+                "456"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn health_checks() {
+        let (client, mut state) = setup(ServerConfig::default()).await;
+        let res = client.get("/livez").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await, "ok");
+
+        let res = client.get("/healthz").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let health: Health = res.json().await;
+        assert_eq!(health.status, "ok");
+        assert!(health.database.ok && health.statistics.ok && health.linter.ok);
+        assert!(health.database.error.is_none());
+
+        state.stats_file_path = "./missing_statistics.json".into();
+        let client = TestClient::new(app(&state));
+        let res = client.get("/healthz").send().await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let health: Health = res.json().await;
+        assert_eq!(health.status, "unhealthy");
+        assert!(health.database.ok && health.linter.ok);
+        assert!(!health.statistics.ok);
+        assert!(health.statistics.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn server_metrics() {
+        let (client, _) = setup(ServerConfig::default()).await;
+        client.get("/api/tool/test-server-metrics").send().await;
+        client.get("/api/tool/test-server-metrics-2").send().await;
+        client.get("/api/no-such-endpoint").send().await;
+        client
+            .get("/api/search?query=test-server-metrics")
+            .send()
+            .await;
+
+        let res = client.get("/metrics").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers()["Content-Type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let metrics = res.text().await;
+        let value = |series: &str| -> f64 {
+            metrics
+                .lines()
+                .find_map(|x| x.strip_prefix(&format!("{series} ")))
+                .unwrap_or_else(|| panic!("{series} missing"))
+                .parse()
+                .unwrap()
+        };
+
+        // Requests are counted by route pattern, not by path
+        assert!(metrics.contains("# TYPE biotools_linter_http_requests_total counter"));
+        assert!(
+            value(
+                r#"biotools_linter_http_requests_total{route="/api/tool/:id",method="GET",status="200"}"#
+            ) >= 2.0
+        );
+        assert!(!metrics.contains("test-server-metrics"));
+        assert!(
+            value(
+                r#"biotools_linter_http_requests_total{route="unmatched",method="GET",status="404"}"#
+            ) >= 1.0
+        );
+        assert!(
+            value(
+                r#"biotools_linter_http_request_duration_seconds_bucket{route="/api/tool/:id",method="GET",le="+Inf"}"#
+            ) >= 2.0
+        );
+        assert!(
+            value(
+                r#"biotools_linter_db_query_duration_seconds_count{query="messages_search_page"}"#
+            ) >= 1.0
+        );
+        assert!(value(r#"biotools_linter_lint_jobs{status="queued"}"#) >= 0.0);
+        assert!(value("biotools_linter_messages") >= 0.0);
+    }
+
+    #[tokio::test]
+    async fn otlp_init_with_logger() {
+        // The server logs through env_logger before telemetry is set up
+        env_logger::builder().is_test(true).try_init().ok();
+        std::env::set_var("OTEL_EXPORTER_OTLP_ENDPOINT", "http://127.0.0.1:1");
+
+        // Another test may own the global subscriber, which is an error rather than a panic,
+        // and the log logger is left to env_logger instead of being claimed by a LogTracer
+        telemetry::init().ok();
+        std::env::remove_var("OTEL_EXPORTER_OTLP_ENDPOINT");
+    }
+
+    #[tokio::test]
+    async fn otlp_export() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (layer, mut receiver) = telemetry::OtlpLayer::new();
+        let remote_trace = "4bf92f3577b34da6a3ce929d0e0e4736";
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let request = tracing::info_span!(
+                "http_request",
+                otel.name = "GET /api/tool/:id",
+                otel.kind = "server",
+                otel.status_code = tracing::field::Empty,
+                traceparent = format!("00-{remote_trace}-00f067aa0ba902b7-01").as_str(),
+                http.response.status_code = tracing::field::Empty,
+            );
+            request.in_scope(|| {
+                tracing::info_span!("get_messages_tool", otel.kind = "client", rows = 3)
+                    .in_scope(|| {});
+            });
+            request.record("http.response.status_code", 500);
+            request.record("otel.status_code", "ERROR");
+        });
+        let mut spans = Vec::new();
+        while let Ok(span) = receiver.try_recv() {
+            spans.push(span);
+        }
+        assert_eq!(spans.len(), 2);
+
+        // Mock collector keeping the exported bodies
+        type Exports = Arc<Mutex<Vec<(Option<String>, serde_json::Value)>>>;
+        let exports: Exports = Arc::default();
+        let collector = Router::new()
+            .route(
+                "/v1/traces",
+                post(
+                    |axum::extract::State(exports): axum::extract::State<Exports>,
+                     headers: axum::http::HeaderMap,
+                     axum::Json(body): axum::Json<serde_json::Value>| async move {
+                        let key = headers
+                            .get("x-api-key")
+                            .map(|x| x.to_str().unwrap().to_owned());
+                        exports.lock().unwrap().push((key, body));
+                    },
+                ),
+            )
+            .with_state(exports.clone());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = telemetry::OtlpConfig {
+            traces_endpoint: format!("http://{}/v1/traces", listener.local_addr().unwrap()),
+            headers: vec![("x-api-key".to_owned(), "collector".to_owned())],
+            service_name: "test-otlp".to_owned(),
+        };
+        tokio::spawn(serve(collector, listener, None));
+        telemetry::send_batch(&reqwest::Client::new(), &config, &spans).await;
+
+        let exports = exports.lock().unwrap();
+        let (key, body) = &exports[0];
+        assert_eq!(key.as_deref(), Some("collector"));
+        let resource = &body["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "test-otlp"
+        );
+        let spans = resource["scopeSpans"][0]["spans"].as_array().unwrap();
+        let span = |name: &str| spans.iter().find(|x| x["name"] == name).unwrap();
+        let (request, query) = (span("GET /api/tool/:id"), span("get_messages_tool"));
+
+        // The request continues the remote trace and the query is its child
+        assert_eq!(request["traceId"], remote_trace);
+        assert_eq!(request["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(request["kind"], 2);
+        assert_eq!(request["status"]["code"], 2);
+        assert_eq!(
+            request["attributes"][0],
+            serde_json::json!({"key": "http.response.status_code", "value": {"intValue": "500"}})
+        );
+        assert_eq!(query["traceId"], remote_trace);
+        assert_eq!(query["parentSpanId"], request["spanId"]);
+        assert_eq!(query["kind"], 3);
+        assert_eq!(query["status"]["code"], 0);
+    }
+
+    #[tokio::test]
+    async fn request_ids() {
+        let (client, _) = setup(ServerConfig::default()).await;
+
+        // Generated when missing, a different one for every request
+        let res = client.get("/api/version").send().await;
+        let first = res.headers()["X-Request-Id"].to_str().unwrap().to_owned();
+        assert_eq!(first.len(), 32);
+        let res = client.get("/api/version").send().await;
+        assert_ne!(res.headers()["X-Request-Id"], first.as_str());
+
+        // A client's ID is kept, invalid ones are replaced
+        let res = client
+            .get("/api/version")
+            .header("X-Request-Id", "client-req.42")
+            .send()
+            .await;
+        assert_eq!(res.headers()["X-Request-Id"], "client-req.42");
+        let res = client
+            .get("/api/version")
+            .header("X-Request-Id", "not valid\"")
+            .send()
+            .await;
+        assert_ne!(res.headers()["X-Request-Id"], "not valid\"");
+        assert_eq!(res.headers()["X-Request-Id"].len(), 32);
+
+        // Failed API requests carry the ID in their error
+        let res = client
+            .get("/api/tool/not%20a%20tool/score")
+            .header("X-Request-Id", "client-req-43")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.headers()["X-Request-Id"], "client-req-43");
+        let body: ApiErrorResponse = res.json().await;
+        assert_eq!(body.error.request_id.as_deref(), Some("client-req-43"));
+    }
+
+    #[tokio::test]
+    async fn autolink_escapes_text() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        delete_messages(&state.pool, "test-autolink-xss").await;
+        sqlx::query(
+            "INSERT INTO messages (time, tool, code, location, text, level) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(1_700_000_000_i64)
+        .bind("test-autolink-xss")
+        .bind("URL_INVALID")
+        .bind("test")
+        .bind("<script>alert(1)</script> https://example.com/?a=1&b='x'<img src=x>")
+        .bind(5)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        let res = client
+            .get("/api/search?query=test-autolink-xss")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let json: serde_json::Value = res.json().await;
+        let html = json["results"][0]["html"].as_str().unwrap();
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<img"));
+        assert!(html.starts_with("&lt;script&gt;alert(1)&lt;/script&gt; <a href=\""));
+        let href = html.split('"').nth(1).unwrap();
+        assert!(!href.contains(['<', '>']));
+        assert!(href.starts_with("https://example.com/?a=1&amp;b="));
+    }
+
+    #[tokio::test]
+    async fn tool_lookup_uses_index() {
+        let (_, state) = setup(ServerConfig::default()).await;
+        let plan = generic_plan(&state.pool, db::messages_tool_query("samtools")).await;
+        assert!(plan.contains("messages_tool_lower"), "{plan}");
+        assert!(plan.contains("lower(tool) = $1"), "{plan}");
+    }
+
+    #[tokio::test]
+    async fn download_database_error() {
+        let (_, state) = setup(ServerConfig::default()).await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(app(&state), listener, None));
+        // The query of the download fails after the status and header were sent
+        state.pool.close().await;
+
+        let res = reqwest::get(format!("http://{addr}/api/download"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        // The body is cut off with an error rather than ending like a complete file
+        assert!(res.text().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn search_unknown_levels() {
+        let (client, state) = setup(ServerConfig::default()).await;
+        insert_messages(
+            &state.pool,
+            "test-unlisted-levels",
+            &[("URL_INVALID", 5), ("URL_INVALID", 0), ("URL_INVALID", 9)],
+        )
+        .await;
+
+        // Levels this server doesn't know are only left out by an explicit severity
+        for (severity, count) in [("", 3), ("&severity=!LinterError", 3), ("&severity=5", 1)] {
+            let res = client
+                .get(&format!(
+                    "/api/search?query=test-unlisted-levels&count_only=true{severity}"
+                ))
+                .send()
+                .await;
+            assert_eq!(res.json::<SearchCount>().await.count, count, "{severity}");
+            let res = client
+                .get(&format!("/api/search?query=test-unlisted-levels{severity}"))
+                .send()
+                .await;
+            let page = res.json::<ApiResponse>().await;
+            assert_eq!(page.results.len() as i64, count, "{severity}");
+        }
+        let res = client
+            .get("/api/search?count_only=true&severity=!ReportHigh")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        delete_messages(&state.pool, "test-unlisted-levels").await;
+    }
+
+    #[tokio::test]
+    async fn search_code_literal_underscore() {
+        use crate::api::CodeFilter;
+
+        let (client, state) = setup(ServerConfig::default()).await;
+        let tool = "test-code-underscore";
+        insert_messages(
+            &state.pool,
+            tool,
+            &[("EDAM_OBSOLETE", 6), ("EDAMXOBSOLETE", 6), ("URL_100%", 6)],
+        )
+        .await;
+
+        // `_` and `%` match themselves, only `*` is a wildcard
+        for (filter, count) in [
+            ("EDAM_OBSOLETE", 1),
+            ("EDAM*OBSOLETE", 2),
+            ("URL_100%25", 1),
+            ("URL_1%25", 0),
+        ] {
+            let res = client
+                .get(&format!("/api/search?query={tool}&code={filter}"))
+                .send()
+                .await;
+            assert_eq!(
+                res.json::<serde_json::Value>().await["count"],
+                count,
+                "{filter}"
+            );
+        }
+        assert_eq!(
+            CodeFilter::parse("EDAM_OBSOLETE").range(),
+            ("EDAM_OBSOLETE".to_owned(), "EDAM_OBSOLETF".to_owned())
+        );
+
+        delete_messages(&state.pool, tool).await;
     }
 }
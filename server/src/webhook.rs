@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::{Pool, Postgres};
+use tracing::{error, info};
+
+use crate::{api::Message, db, ServerState};
+
+/// Most findings sent in one delivery, the rest follow on the next run
+const WEBHOOK_BATCH: i64 = 1000;
+
+/// Attempts of a delivery before it is left for the next run
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// Longest a subscribed URL may take to answer one attempt
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Seconds a finding waits before it is delivered
+///
+/// IDs and times are taken when the linter inserts a finding, but become visible when its
+/// transaction commits. By the time a finding is this old, every finding found before it has
+/// committed, so the cursor never moves past one that shows up later.
+pub const WEBHOOK_SAFETY_LAG: i64 = 5 * 60;
+
+/// Header holding `sha256=<hex HMAC-SHA256 of the body>` keyed with the subscription secret
+pub const SIGNATURE_HEADER: &str = "X-Linter-Signature";
+
+/// Time and ID of a finding, the order findings are delivered in
+pub type Cursor = (i64, i32);
+
+/// A webhook subscription as stored in the database
+#[derive(Debug)]
+pub struct WebhookSubscription {
+    pub id: i32,
+    pub url: String,
+    /// Normalized biotoolsID, `None` for every tool
+    pub tool: Option<String>,
+    /// Error code, `None` for every code
+    pub code: Option<String>,
+    pub secret: String,
+    /// Time of the newest finding delivered
+    pub last_time: i64,
+    /// ID of the newest finding delivered, breaking ties of `last_time`
+    pub last_id: i32,
+}
+
+/// Body POSTed to the subscribed URL
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    subscription: i32,
+    tool: Option<&'a str>,
+    code: Option<&'a str>,
+    /// New findings in the order they were found
+    messages: Vec<Message>,
+}
+
+/// Random secret the deliveries of a new subscription are signed with
+pub fn generate_secret() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Value of the signature header of `body`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect();
+    format!("sha256={digest}")
+}
+
+/// Periodically send the findings found since the last delivery to every subscription
+pub async fn deliver_new_messages(state: ServerState, interval: Duration) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .unwrap();
+    let mut timer = tokio::time::interval(interval);
+
+    loop {
+        timer.tick().await;
+        deliver_pending(
+            &state.pool,
+            &client,
+            &state.config.tool_url_base,
+            state.clock.now(),
+        )
+        .await;
+    }
+}
+
+/// Deliver one batch of new findings to each subscription at once, tools are linked under `tool_url_base`
+///
+/// Findings are delivered [`WEBHOOK_SAFETY_LAG`] seconds after `now`. A subscription only advances
+/// once its URL accepted the batch, so failed deliveries are retried on the next run and no
+/// findings are skipped.
+pub async fn deliver_pending(
+    pool: &Pool<Postgres>,
+    client: &reqwest::Client,
+    tool_url_base: &str,
+    now: i64,
+) {
+    let subscriptions = match db::webhooks(pool).await {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Could not load webhook subscriptions: {e}");
+            return;
+        }
+    };
+
+    // A slow URL only delays its own subscription
+    join_all(subscriptions.iter().map(|subscription| {
+        deliver_batch(
+            pool,
+            client,
+            tool_url_base,
+            now - WEBHOOK_SAFETY_LAG,
+            subscription,
+        )
+    }))
+    .await;
+}
+
+/// Deliver the findings of one subscription found no later than `until`
+async fn deliver_batch(
+    pool: &Pool<Postgres>,
+    client: &reqwest::Client,
+    tool_url_base: &str,
+    until: i64,
+    subscription: &WebhookSubscription,
+) {
+    let tool = subscription.tool.as_deref();
+    let code = subscription.code.as_deref();
+    let after = (subscription.last_time, subscription.last_id);
+    let rows = match db::messages_after(pool, after, until, tool, code, WEBHOOK_BATCH).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!(
+                "Could not load findings of webhook {}: {e}",
+                subscription.id
+            );
+            return;
+        }
+    };
+    let Some(&(last, _)) = rows.last() else {
+        return;
+    };
+
+    let payload = WebhookPayload {
+        subscription: subscription.id,
+        tool,
+        code,
+        messages: rows
+            .into_iter()
+            .map(|(_, mut message)| {
+                message.link_tool(tool_url_base);
+                message
+            })
+            .collect(),
+    };
+    let body = serde_json::to_vec(&payload).unwrap();
+
+    if send(client, subscription, body).await {
+        info!(
+            "Delivered {} findings to webhook {}",
+            payload.messages.len(),
+            subscription.id
+        );
+        db::advance_webhook(pool, subscription.id, last).await;
+    }
+}
+
+/// POST a signed body, retrying with a doubling delay, returns whether the URL accepted it
+async fn send(client: &reqwest::Client, subscription: &WebhookSubscription, body: Vec<u8>) -> bool {
+    let signature = sign(&subscription.secret, &body);
+    let mut delay = Duration::from_secs(1);
+
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let result = client
+            .post(&subscription.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .send()
+            .await;
+        match result {
+            Ok(res) if res.status().is_success() => return true,
+            Ok(res) => error!(
+                "Webhook {} attempt {attempt} got status {}",
+                subscription.id,
+                res.status()
+            ),
+            Err(e) => error!("Webhook {} attempt {attempt} failed: {e}", subscription.id),
+        }
+        if attempt < WEBHOOK_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+    false
+}